@@ -1,25 +1,34 @@
 use crate::app_global::{IS_COLORFUL, THEME};
-use crate::config::Config;
+use crate::bookmark::{self, Bookmarks};
+use crate::command;
+use crate::config::{self, Config, ExportConfig};
+use crate::draw::DetailLayout;
 use crate::events::{self, warn, Message};
+use crate::export;
 use crate::fetch;
-use crate::parse::PARSER;
+use crate::history::History;
+use crate::index;
+use crate::keymap::Keymap;
+use crate::note::Notes;
+use crate::parse::LastParse;
+use crate::readme;
+use crate::screenshot::{self, Graphics};
+use crate::seen::Seen;
+use crate::store::Store;
+use crate::terminal;
 use crate::theme::{Theme, THEME_STYLE};
-use crate::utils::parse_unchecked;
-use crate::widget::content::Category;
+use crate::widget::completion::CompletionState;
+use crate::widget::content::{Category, Project};
+use crate::widget::editor::EditorState;
 use crate::widget::projectdetail::ProjectDetailState;
 use crate::widget::{ContentState, InputState, PopupState, StatusLineState};
+use crate::worker::{self, Job, JobResult};
 
-use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-
-use anyhow::Result;
-use std::fs::File;
-use std::path::Path;
+use anyhow::{anyhow, Context, Result};
 use std::{
-    io::{self, Stdout},
+    fs,
+    io::Stdout,
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 
@@ -35,6 +44,12 @@ pub enum SearchMode {
 
     /// 搜类别
     Category,
+
+    /// 收藏夹
+    Favorite,
+
+    /// 按标签搜索
+    Tag,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -50,6 +65,9 @@ pub enum AppMode {
 
     /// 项目明细
     Detail,
+
+    /// 编辑笔记
+    Edit,
 }
 
 pub struct App {
@@ -79,18 +97,80 @@ pub struct App {
 
     /// 是否要显示帮助
     pub show_help: bool,
+
+    /// 启动后默认执行的搜索
+    pub startup: Option<String>,
+
+    /// 浏览模式下 u/d 移动的行数
+    pub page_size: usize,
+
+    /// 快捷键
+    pub keymap: Keymap,
+
+    /// 导出设置
+    export: ExportConfig,
+
+    /// 当前展示的结果来自哪个页面，导出时使用
+    last_parse: Option<LastParse>,
+
+    /// 当前列表的搜索模式，翻页时按它请求，和输入框里正在输入的模式无关
+    list_mode: SearchMode,
+
+    /// 收藏夹
+    bookmarks: Store<Bookmarks>,
+
+    /// 收藏夹的过滤关键字或者要找的标签
+    local_keyword: String,
+
+    /// 按 `:` 跳转前输入框的内容和搜索模式，取消跳转时还原
+    jump_from: Option<(String, SearchMode)>,
+
+    /// 笔记和标签
+    notes: Store<Notes>,
+
+    /// 编辑笔记时的标签输入框
+    pub tags_editor: EditorState,
+
+    /// 编辑笔记时的笔记输入框
+    pub note_editor: EditorState,
+
+    /// 看过的项目
+    pub seen: Store<Seen>,
+
+    /// 已读记录保存失败时只提示一次
+    seen_warned: bool,
+
+    /// 搜索记录
+    pub history: Store<History>,
+
+    /// 输入框下方的补全
+    pub completion: CompletionState,
+
+    /// 详情页是否展示截图
+    show_image: bool,
+
+    /// 用 kitty、sixel 协议显示截图，只能用半格字符时为 None
+    pub graphics: Option<Graphics>,
+
+    /// 详情页的布局
+    pub layout: DetailLayout,
+
+    /// 配置文件路径，切换布局时写回
+    config_path: PathBuf,
+
+    /// 最近一次请求的编号
+    request_id: u64,
+
+    /// 正在进行的请求编号
+    loading: Option<u64>,
 }
 
 impl App {
     fn new(config: &Config) -> Result<App> {
-        let show_help = Self::init_config(config.config_path.clone())? || config.show_help;
+        fetch::init(&config.network, &config.cache)?;
+        index::init(&config.index, &config.data_dir)?;
 
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.clear()?;
+        let app = App::build(config, || Ok(terminal::open(config.mouse)?))?;
 
         // init Global static
         THEME
@@ -104,6 +184,22 @@ impl App {
 
         IS_COLORFUL.store(is_colorful, std::sync::atomic::Ordering::Relaxed);
 
+        Ok(app)
+    }
+
+    /// 读取本地数据后再调用 `open_terminal` 进入界面，读取失败时终端保持原样
+    fn build(
+        config: &Config,
+        open_terminal: impl FnOnce() -> Result<Terminal<CrosstermBackend<Stdout>>>,
+    ) -> Result<App> {
+        let keymap = Keymap::new(&config.keybindings)?;
+        let bookmarks = Store::open(config.data_dir.join("bookmarks.json"))?;
+        let seen = Store::open(config.data_dir.join("seen.json"))?;
+        let notes = Store::open(config.data_dir.join("notes.json"))?;
+        let history = Store::open(config.data_dir.join("history.json"))?;
+
+        let terminal = open_terminal()?;
+
         Ok(App {
             terminal,
             input: InputState::default(),
@@ -113,61 +209,258 @@ impl App {
             mode: AppMode::Search,
             curr_category: None,
             project_detail: ProjectDetailState::default(),
-            show_help,
+            show_help: config.show_help,
+            startup: config.startup.clone(),
+            page_size: config.page_size,
+            keymap,
+            export: config.export.clone(),
+            last_parse: None,
+            list_mode: SearchMode::Normal,
+            bookmarks,
+            local_keyword: String::new(),
+            jump_from: None,
+            notes,
+            tags_editor: EditorState::default(),
+            note_editor: EditorState::default(),
+            seen,
+            seen_warned: false,
+            history,
+            completion: CompletionState::default(),
+            show_image: config.image.enabled,
+            graphics: Graphics::new(config.image.protocol),
+            layout: config.layout,
+            config_path: config.config_path.clone(),
+            request_id: 0,
+            loading: None,
         })
     }
+}
+
+impl App {
+    /// 启动后执行一次搜索，默认展示最新一期
+    pub fn startup(&mut self) {
+        match self.startup.clone() {
+            Some(startup) => {
+                for c in startup.chars() {
+                    let mode = self.input.handle_char(c);
+                    self.statusline.set_mode(mode);
+                }
+                self.search(None);
+            }
+            None => {
+                self.input.mode = SearchMode::Volume;
+                self.statusline.set_mode(SearchMode::Volume);
+                self.request(Job::Latest);
+            }
+        }
+    }
 
-    /// 初始化配置文件
-    fn init_config(config_path: String) -> Result<bool> {
-        let path = Path::new(&config_path).join(".hgtui.toml");
-        if path.exists() {
-            return Ok(false);
+    /// Tab 补全，切换到下一个（`forward` 为假时上一个）候选
+    pub fn complete(&mut self, forward: bool) {
+        if let Some(text) = self.completion.cycle(forward) {
+            let mode = self.input.replace(text);
+            self.statusline.set_mode(mode);
         }
-        if File::create(&path).is_ok() {
-            return Ok(true);
+    }
+
+    /// 提交输入框里的内容，记入搜索记录后搜索
+    pub fn submit_search(&mut self) -> Result<()> {
+        let query = self.input.clear();
+        if query.trim().is_empty() {
+            return Ok(());
         }
-        Ok(false)
+        self.jump_from = None;
+        // 搜索记录保存失败也照常搜索
+        let saved = self.history.update(|history| history.push(&query));
+        self.search(Some(query));
+        saved
     }
-}
 
-impl App {
-    pub fn search(&mut self, wait_search: Option<String>) -> Result<()> {
+    pub fn search(&mut self, wait_search: Option<String>) {
         if self.input.is_empty() && wait_search.is_none() {
             // 输入框为空直接返回
-            return Ok(());
+            return;
         }
         let search_mode = self.input.mode;
 
         let wait_search = wait_search.unwrap_or_else(|| self.input.clear());
 
-        let wait_remove = wait_search.clone();
+        match search_mode {
+            SearchMode::Favorite => {
+                let keyword = bookmark::keyword(&wait_search).to_string();
+                self.show_local(search_mode, keyword, 1);
+                return;
+            }
+            SearchMode::Tag => {
+                let tag = wait_search[1..].trim().to_string();
+                self.show_local(search_mode, tag, 1);
+                return;
+            }
+            _ => {}
+        }
+
+        self.request(Job::Search(wait_search, search_mode));
+    }
+
+    /// 展示收藏夹、标签这类保存在本地的列表，不需要后台请求
+    fn show_local(&mut self, mode: SearchMode, keyword: String, page_no: usize) {
+        let (projects, _) = self.local_page(mode, &keyword, page_no);
+        if projects.is_empty() {
+            if page_no <= 1 {
+                warn(match (mode, keyword.is_empty()) {
+                    (SearchMode::Tag, true) => {
+                        "还没有打过标签的项目，在详情页按 i 添加笔记和标签".into()
+                    }
+                    (SearchMode::Tag, false) => format!("没有标签为 {} 的项目", keyword),
+                    (_, true) => "收藏夹是空的，浏览时按 b 收藏项目".into(),
+                    (_, false) => format!("收藏夹里没有和 {} 相关的项目", keyword),
+                });
+            }
+            return;
+        }
+        self.cancel();
+        self.last_parse = Some(match mode {
+            SearchMode::Tag => LastParse::Tag(keyword.clone()),
+            _ => LastParse::Favorite,
+        });
+        self.local_keyword = keyword;
+        self.curr_category = None;
+        self.list_mode = mode;
+        self.content.add_projects(projects);
+        self.content.tstate.select(Some(0));
+        self.statusline.set_mode(mode);
+        self.statusline.set_page_no(page_no);
+        if self.mode != AppMode::Popup {
+            self.switch_to_view();
+        }
+    }
+
+    fn local_page(&self, mode: SearchMode, keyword: &str, page_no: usize) -> (Vec<Project>, usize) {
+        match mode {
+            SearchMode::Tag => self.notes.get().page(keyword, page_no),
+            _ => self.bookmarks.get().page(keyword, page_no),
+        }
+    }
+
+    /// 本地列表的内容变了（如取消收藏），刷新当前页，最后一页删空了就往前翻一页
+    fn refresh_local(&mut self) {
+        let mode = self.list_mode;
+        let page_no = self.statusline.page_no();
+        let (projects, pages) = self.local_page(mode, &self.local_keyword, page_no);
+        if projects.is_empty() && pages > 0 {
+            self.show_local(mode, self.local_keyword.clone(), pages);
+        } else {
+            let selected = self.content.tstate.selected();
+            self.content.add_projects(projects);
+            self.content.tstate.select(selected);
+            self.content.clamp_selected();
+        }
+    }
+
+    /// 收藏或取消收藏选中的项目，返回提示信息
+    pub fn toggle_bookmark(&mut self) -> Result<String> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        let name = project.name.clone();
+        let added = self
+            .bookmarks
+            .update(|bookmarks| bookmarks.toggle(project))?;
+
+        if !added && self.list_mode == SearchMode::Favorite {
+            self.refresh_local();
+        }
+
+        Ok(if added {
+            format!("已收藏 {}，输入 @fav 查看收藏夹", name)
+        } else {
+            format!("已取消收藏 {}", name)
+        })
+    }
+
+    /// 在后台发起请求，之前未完成的请求结果会被丢弃
+    fn request(&mut self, job: Job) {
+        self.request_id += 1;
+        self.loading = Some(self.request_id);
+        self.statusline.set_loading(true);
+        worker::spawn(self.request_id, job);
+    }
+
+    /// 取消正在进行的请求，返回是否有请求被取消
+    pub fn cancel(&mut self) -> bool {
+        self.statusline.set_loading(false);
+        self.loading.take().is_some()
+    }
+
+    /// 后台请求完成
+    pub fn finish(&mut self, id: u64, job: Job, result: JobResult) -> Result<()> {
+        if self.loading != Some(id) {
+            // 已经取消或者被新的请求覆盖
+            return Ok(());
+        }
+        self.cancel();
+
+        let (projects, last_parse) = result.map_err(|e| anyhow!(e))?;
+
+        match job {
+            Job::Latest => {
+                self.finish_search(String::new(), SearchMode::Volume, projects, last_parse)
+            }
+            Job::Search(wait_search, search_mode) => {
+                self.finish_search(wait_search, search_mode, projects, last_parse)
+            }
+            Job::MarkVolume(_) => {
+                self.mark_seen(projects.iter().map(|p| p.url.as_str()));
+            }
+            Job::Page(_, _, page_no) => {
+                if projects.is_empty() {
+                    // 翻过了最后一页，留在当前页
+                    warn("没有更多了".into());
+                    return Ok(());
+                }
+                self.last_parse = Some(last_parse);
+                self.content.add_projects(projects);
+                self.content.tstate.select(Some(0));
+                self.statusline.set_page_no(page_no);
+            }
+        }
 
-        let text = fetch::fetch(wait_search, search_mode)?;
+        Ok(())
+    }
 
-        let (projects, last_parse) = PARSER.get(&search_mode).unwrap().parse(text)?;
+    fn finish_search(
+        &mut self,
+        wait_remove: String,
+        search_mode: SearchMode,
+        projects: Vec<Project>,
+        last_parse: LastParse,
+    ) {
         if projects.is_empty() {
             warn("无结果返回，请确认搜索关键字".into());
-            return Ok(());
+            return;
         }
 
         let wait_remove = match last_parse {
-            crate::parse::LastParse::Volume(v) => {
-                format!("#{}", parse_unchecked(&v, 1))
-            }
+            LastParse::Volume(volume) => format!("#{}", volume),
             _ => wait_remove,
         };
+        self.last_parse = Some(last_parse);
+        self.list_mode = search_mode;
 
         let mut category_change = false;
 
-        if search_mode == SearchMode::Category {
-            let category = Category::try_from(wait_remove[1..].to_string()).unwrap();
-            if let Some(prev_category) = self.curr_category {
-                category_change = prev_category != category;
+        // 类别在发起请求前已经校验过，只有搜类别时关键字才以 $ 开头
+        let category = match wait_remove.strip_prefix('$') {
+            Some(name) if search_mode == SearchMode::Category => {
+                Category::try_from(name.to_string()).ok()
             }
-            self.curr_category = Some(category);
-        } else {
-            self.curr_category = None;
+            _ => None,
+        };
+        if let (Some(prev_category), Some(category)) = (self.curr_category, category) {
+            category_change = prev_category != category;
         }
+        self.curr_category = category;
 
         if category_change {
             self.statusline.set_page_no(1);
@@ -178,13 +471,21 @@ impl App {
 
         self.content.add_projects(projects);
 
-        // 搜索完自动切换到浏览模式
-        self.switch_to_view();
-
-        Ok(())
+        // 搜索完自动切换到浏览模式，正在展示的弹窗（如首次启动的帮助）保留
+        if self.mode == AppMode::Popup {
+            self.input.deactive();
+            self.content.active();
+        } else {
+            self.switch_to_view();
+        }
     }
 
     pub fn switch_to_view(&mut self) {
+        if let Some((text, mode)) = self.jump_from.take() {
+            self.input.replace(&text);
+            self.input.mode = mode;
+            self.statusline.set_mode(mode);
+        }
         self.input.deactive();
         self.content.active();
         self.mode = AppMode::View;
@@ -196,64 +497,243 @@ impl App {
         self.mode = AppMode::Search;
     }
     pub fn popup(&mut self, msg: Message) {
-        self.popup.msg = msg;
+        self.popup = PopupState::new(msg);
         self.mode = AppMode::Popup;
     }
-    pub fn next_page(&mut self) -> Result<()> {
-        self.page(self.statusline.page_no() + 1)?;
+    /// 往后翻 `count` 页
+    pub fn next_page(&mut self, count: usize) {
+        self.page(self.statusline.page_no().saturating_add(count));
+    }
 
-        Ok(())
+    /// 往前翻 `count` 页，超过第一页时停在第一页
+    pub fn prev_page(&mut self, count: usize) {
+        let page_no = self.statusline.page_no();
+        if page_no > 1 {
+            self.page(page_no.saturating_sub(count).max(1));
+        }
     }
 
-    pub fn prev_page(&mut self) -> Result<()> {
-        self.page(self.statusline.page_no() - 1)?;
+    /// 浏览模式下按 `:` 输入期数跳转，如 `:72`
+    pub fn jump_volume(&mut self) {
+        self.jump_from = Some((self.input.text().to_string(), self.input.mode));
+        self.switch_to_search();
+        let mode = self.input.replace(":");
+        self.statusline.set_mode(mode);
+    }
+
+    fn page(&mut self, page_no: usize) {
+        if page_no < 1 || self.list_mode == SearchMode::Normal {
+            return;
+        }
+        if matches!(self.list_mode, SearchMode::Favorite | SearchMode::Tag) {
+            self.show_local(self.list_mode, self.local_keyword.clone(), page_no);
+            return;
+        }
+        self.request(Job::Page(self.list_mode, self.curr_category, page_no));
+    }
 
+    pub fn display_detail(&mut self) -> Result<()> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        self.mark_seen([project.url.as_str()]);
+        // 分栏时详情已经跟着选中的项目加载好了，保留 README 的滚动位置
+        if project.url != self.project_detail.url() {
+            self.load_detail(project, false);
+        }
+        self.mode = AppMode::Detail;
         Ok(())
     }
 
-    fn page(&mut self, page_no: usize) -> Result<()> {
-        let text = match self.input.mode {
-            SearchMode::Volume => fetch::fetch_volume(page_no),
-            SearchMode::Category => fetch::fetch_category(self.curr_category.unwrap(), page_no),
-            _ => {
-                return Ok(());
+    /// 加载详情，`preview` 为真时是分栏预览，截图等选中的项目停下来再下载
+    fn load_detail(&mut self, project: Project, preview: bool) {
+        let note = self.notes.get().get(&project.url);
+        self.project_detail = project.into();
+        self.project_detail.set_note(note);
+        if let Some(src) = self.project_detail.image_src().filter(|_| self.show_image) {
+            if preview {
+                screenshot::preview(src.to_string());
+            } else {
+                screenshot::spawn(src.to_string());
+            }
+            self.project_detail.set_loading();
+        }
+    }
+
+    /// 分栏布局时详情跟着选中的项目变化，只是预览，不标记为已读
+    pub fn sync_preview(&mut self) {
+        if self.layout == DetailLayout::Overlay
+            || matches!(self.mode, AppMode::Detail | AppMode::Edit)
+        {
+            return;
+        }
+        match self.content.get_selected() {
+            Some(project) if project.url != self.project_detail.url() => {
+                self.load_detail(project, true)
             }
-        };
-        let (projects, _) = PARSER.get(&self.input.mode).unwrap().parse(text)?;
-        self.content.add_projects(projects);
-        self.content.tstate.select(Some(0));
-        self.statusline.set_page_no(page_no);
-        // 搜索完自动切换到浏览模式
-        // self.switch_to_view();
+            Some(_) => {}
+            None => self.project_detail = ProjectDetailState::default(),
+        }
+    }
+
+    /// 按 覆盖、左右分栏、上下分栏 的顺序切换详情页布局，并写回配置文件
+    pub fn toggle_layout(&mut self) -> Result<()> {
+        self.layout = self.layout.next();
+        config::save_layout(&self.config_path, self.layout)
+    }
+
+    /// 在详情页切换 README，第一次打开时在后台下载
+    pub fn toggle_readme(&mut self) {
+        if let Some(url) = self.project_detail.toggle_readme() {
+            readme::spawn(url);
+        }
+    }
 
+    /// 在详情页编辑选中项目的笔记和标签
+    pub fn edit_note(&mut self) -> Result<()> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        let note = self
+            .notes
+            .get()
+            .get(&project.url)
+            .cloned()
+            .unwrap_or_default();
+        self.tags_editor = EditorState::new(&note.tags.join(" "), false);
+        self.note_editor = EditorState::new(&note.text, true);
+        self.note_editor.set_active(true);
+        self.mode = AppMode::Edit;
         Ok(())
     }
 
-    pub fn display_detail(&mut self) -> Result<()> {
+    /// 正在编辑的输入框
+    pub fn editor(&mut self) -> &mut EditorState {
+        if self.editing_tags() {
+            &mut self.tags_editor
+        } else {
+            &mut self.note_editor
+        }
+    }
+
+    pub fn editing_tags(&self) -> bool {
+        self.tags_editor.is_active()
+    }
+
+    /// 在标签、笔记输入框之间切换
+    pub fn switch_editor(&mut self) {
+        let editing_tags = self.editing_tags();
+        self.tags_editor.set_active(!editing_tags);
+        self.note_editor.set_active(editing_tags);
+    }
+
+    pub fn save_note(&mut self) -> Result<()> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        let url = project.url.clone();
+        let (text, tags) = (self.note_editor.text(), self.tags_editor.text());
+        self.notes
+            .update(|notes| notes.set(project, &text, &tags))?;
+        self.project_detail.set_note(self.notes.get().get(&url));
         self.mode = AppMode::Detail;
-        let project = self.content.get_selected();
-        self.project_detail = project.into();
+
+        if self.list_mode == SearchMode::Tag {
+            self.refresh_local();
+        }
+        Ok(())
+    }
+
+    /// 切换选中项目的已读状态
+    pub fn toggle_seen(&mut self) -> Result<()> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        self.seen.update(|seen| seen.toggle(&project.url))?;
+        Ok(())
+    }
+
+    /// 把选中项目所在的一整期标记为已读，正在看的不是这一期时在后台请求这一期
+    pub fn mark_all_seen(&mut self) -> Result<()> {
+        let project = self
+            .content
+            .get_selected()
+            .ok_or_else(|| anyhow!("没有选中的项目"))?;
+        if self.last_parse == Some(LastParse::Volume(project.volume)) {
+            let projects = self.content.projects().to_vec();
+            self.mark_seen(projects.iter().map(|p| p.url.as_str()));
+        } else {
+            self.request(Job::MarkVolume(project.volume));
+        }
         Ok(())
     }
 
-    pub fn open_browser(&self, url: Option<&str>) -> Result<()> {
-        let project = self.content.get_selected();
-        let url = url.unwrap_or_else(|| project.url.as_ref());
-        webbrowser::open(url)?;
+    /// 标记为已读。保存失败不影响浏览，只提示一次，本次运行期间照样显示为已读
+    fn mark_seen<'a>(&mut self, urls: impl IntoIterator<Item = &'a str>) {
+        if let Err(e) = self.seen.update(|seen| seen.mark_all(urls)) {
+            if !self.seen_warned {
+                self.seen_warned = true;
+                warn(format!("已读记录保存失败：{:#}", e));
+            }
+        }
+    }
+
+    /// 从选中行往下找下一个未读的项目，到底后从头找，返回是否找到
+    pub fn next_unseen(&mut self) -> bool {
+        let projects = self.content.projects();
+        let start = self.content.tstate.selected().map_or(0, |i| i + 1);
+        let next = (0..projects.len())
+            .map(|i| (start + i) % projects.len())
+            .find(|i| !self.seen.get().is_seen(&projects[*i].url));
+        if next.is_some() {
+            self.content.tstate.select(next);
+        }
+        next.is_some()
+    }
+
+    /// 把当前结果导出到文件，返回文件路径
+    pub fn export(&self) -> Result<PathBuf> {
+        let last_parse = match &self.last_parse {
+            Some(last_parse) if !self.content.projects().is_empty() => last_parse,
+            _ => return Err(anyhow!("没有可以导出的项目")),
+        };
+        let text = export::export(self.content.projects(), last_parse, self.export.format)?;
+
+        let dir = self.export.dir.clone().unwrap_or_default();
+        let path = export::unique_path(
+            &dir,
+            &export::file_stem(last_parse, self.statusline.page_no()),
+            self.export.format.extension(),
+        );
+        fs::write(&path, text).with_context(|| format!("无法写入 {}", path.display()))?;
+        Ok(path)
+    }
+
+    pub fn open_browser(&mut self, url: Option<&str>) -> Result<()> {
+        let url = match url {
+            Some(url) => url.to_string(),
+            None => {
+                let url = self
+                    .content
+                    .get_selected()
+                    .ok_or_else(|| anyhow!("没有选中的项目"))?
+                    .url;
+                self.mark_seen([url.as_str()]);
+                url
+            }
+        };
+        webbrowser::open(&url)?;
         Ok(())
     }
 }
 
 impl Drop for App {
     fn drop(&mut self) {
-        disable_raw_mode().unwrap();
-        execute!(
-            self.terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )
-        .unwrap();
-        self.terminal.show_cursor().unwrap();
+        terminal::restore();
     }
 }
 
@@ -263,7 +743,13 @@ pub(crate) fn start(config: &Config) -> Result<()> {
         return Ok(());
     }
 
+    if let Some(command) = &config.command {
+        return command::run(command, config);
+    }
+
     let app = Arc::new(Mutex::new(App::new(config)?));
+    terminal::handle_signals()?;
+    app.lock().unwrap().startup();
 
     let moved_app = app.clone();
     events::handle_key_event(moved_app);
@@ -272,3 +758,184 @@ pub(crate) fn start(config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::cli::Args;
+    use crate::utils::LOCAL_PAGE_SIZE;
+    use clap::Parser;
+    use tui::{layout::Rect, TerminalOptions, Viewport};
+
+    /// 不进入终端的 App，数据放在临时目录
+    pub fn app(name: &str) -> App {
+        let dir = std::env::temp_dir().join(format!("hgtui-app-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".hgtui.toml"), format!("data_dir = {:?}\n", dir)).unwrap();
+        let args = Args::parse_from(["hgtui", "-p", dir.to_str().unwrap()]);
+        let config = Config::load(args).unwrap();
+        App::build(&config, || {
+            let options = TerminalOptions {
+                viewport: Viewport::fixed(Rect::new(0, 0, 80, 24)),
+            };
+            Ok(Terminal::with_options(
+                CrosstermBackend::new(std::io::stdout()),
+                options,
+            )?)
+        })
+        .unwrap()
+    }
+
+    pub fn project(name: &str, category: &str) -> Project {
+        let url = format!("https://github.com/{}", name);
+        Project::new(name, 72, category, &url, "", "", "", "")
+    }
+
+    #[test]
+    fn test_finish_normal_search() {
+        let mut app = app("normal");
+        app.curr_category = Some(Category::Rust);
+        // 关键字第一个字符是多字节字符
+        app.finish_search(
+            "数据库".to_string(),
+            SearchMode::Normal,
+            vec![project("sqlite", "C")],
+            LastParse::Search,
+        );
+        assert_eq!(None, app.curr_category);
+        assert_eq!(1, app.content.projects().len());
+        assert_eq!(AppMode::View, app.mode);
+    }
+
+    #[test]
+    fn test_finish_category_search() {
+        let mut app = app("category");
+        app.finish_search(
+            "$rust".to_string(),
+            SearchMode::Category,
+            vec![project("ripgrep", "Rust")],
+            LastParse::Category("Rust".to_string()),
+        );
+        assert_eq!(Some(Category::Rust), app.curr_category);
+    }
+
+    #[test]
+    fn test_mark_volume_seen() {
+        let mut app = app("volume");
+        app.finish_search(
+            "#72".to_string(),
+            SearchMode::Volume,
+            vec![project("ncdu", "C"), project("black", "Python")],
+            LastParse::Volume(72),
+        );
+        app.content.tstate.select(Some(0));
+        app.mark_all_seen().unwrap();
+        let seen = app.seen.get();
+        assert!(app.content.projects().iter().all(|p| seen.is_seen(&p.url)));
+    }
+
+    #[test]
+    fn test_page_past_last() {
+        let mut app = app("last-page");
+        app.finish_search(
+            "$rust".to_string(),
+            SearchMode::Category,
+            vec![project("ripgrep", "Rust")],
+            LastParse::Category("Rust 项目".to_string()),
+        );
+        app.loading = Some(1);
+        let job = Job::Page(SearchMode::Category, Some(Category::Rust), 2);
+        let result = Ok((vec![], LastParse::Category("Rust 项目".to_string())));
+        app.finish(1, job, result).unwrap();
+
+        // 空页不替换当前列表，页码也不变
+        assert_eq!(1, app.content.projects().len());
+        assert_eq!(1, app.statusline.page_no());
+        assert_eq!(None, app.loading);
+    }
+
+    #[test]
+    fn test_page_keeps_list_mode() {
+        let mut app = app("list-mode");
+        app.bookmarks
+            .update(|bookmarks| {
+                for i in 0..LOCAL_PAGE_SIZE + 1 {
+                    bookmarks.toggle(project(&format!("p{}", i), "C"));
+                }
+            })
+            .unwrap();
+        app.input.replace("@fav");
+        app.search(None);
+        assert_eq!(SearchMode::Favorite, app.list_mode);
+
+        // 输入框换成了普通搜索的内容，翻页还是翻收藏夹
+        app.input.replace("");
+        assert_eq!(SearchMode::Normal, app.input.mode);
+        app.next_page(1);
+        assert_eq!(2, app.statusline.page_no());
+        assert_eq!(1, app.content.projects().len());
+    }
+
+    #[test]
+    fn test_export_favorites() {
+        let mut app = app("export");
+        let dir = std::env::temp_dir().join(format!("hgtui-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        app.export = ExportConfig {
+            format: export::Format::Markdown,
+            dir: Some(dir.clone()),
+        };
+        app.bookmarks
+            .update(|bookmarks| bookmarks.toggle(project("ncdu", "C")))
+            .unwrap();
+        app.input.replace("@fav");
+        app.search(None);
+
+        let first = app.export().unwrap();
+        assert_eq!(dir.join("hellogithub-favorites-1.md"), first);
+        assert!(fs::read_to_string(&first)
+            .unwrap()
+            .starts_with("# HelloGitHub 收藏夹"));
+        // 再导出一次不覆盖之前的文件
+        assert_eq!(
+            dir.join("hellogithub-favorites-1-1.md"),
+            app.export().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cancel_jump_volume() {
+        let mut app = app("jump");
+        app.input.replace("$rust");
+        app.input.clear();
+        app.finish_search(
+            "$rust".to_string(),
+            SearchMode::Category,
+            vec![project("ripgrep", "Rust")],
+            LastParse::Category("Rust 项目".to_string()),
+        );
+
+        // 按 : 之后按 Esc 取消，翻页还是按类别翻
+        app.jump_volume();
+        assert_eq!(SearchMode::Volume, app.input.mode);
+        app.switch_to_view();
+        assert_eq!(SearchMode::Category, app.input.mode);
+        assert!(app.input.is_empty());
+    }
+
+    #[test]
+    fn test_detail_when_seen_store_fails() {
+        let mut app = app("readonly");
+        // 数据目录的位置是一个文件，已读记录保存不了
+        let file = std::env::temp_dir().join(format!("hgtui-app-file-{}", std::process::id()));
+        fs::write(&file, "").unwrap();
+        app.seen = Store::open(file.join("seen.json")).unwrap();
+
+        app.content.add_projects(vec![project("ncdu", "C")]);
+        app.content.tstate.select(Some(0));
+        app.display_detail().unwrap();
+        assert_eq!(AppMode::Detail, app.mode);
+        assert!(app.seen.get().is_seen("https://github.com/ncdu"));
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+use crate::widget::content::Project;
+
+/// 收藏的项目，最近收藏的排在前面，按项目地址去重
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Bookmarks {
+    projects: Vec<Project>,
+}
+
+impl Bookmarks {
+    /// 收藏或取消收藏，返回是否是新收藏的
+    pub fn toggle(&mut self, project: Project) -> bool {
+        let len = self.projects.len();
+        self.projects.retain(|p| p.url != project.url);
+        if self.projects.len() < len {
+            return false;
+        }
+        self.projects.insert(0, project);
+        true
+    }
+
+    /// 按关键字过滤后的某一页和总页数，关键字匹配名称、类别、介绍，不区分大小写
+    pub fn page(&self, keyword: &str, page_no: usize) -> (Vec<Project>, usize) {
+        let keyword = keyword.trim().to_lowercase();
+        let matched: Vec<Project> = self
+            .projects
+            .iter()
+            .filter(|project| {
+                keyword.is_empty()
+                    || [&project.name, &project.category, &project.desc]
+                        .iter()
+                        .any(|text| text.to_lowercase().contains(&keyword))
+            })
+            .cloned()
+            .collect();
+        utils::paginate(&matched, page_no)
+    }
+}
+
+/// 从 `@fav rust` 这样的输入中取出过滤关键字
+pub fn keyword(input: &str) -> &str {
+    let input = input.strip_prefix('@').unwrap_or(input);
+    input.strip_prefix("fav").unwrap_or(input).trim()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::LOCAL_PAGE_SIZE;
+
+    fn project(name: &str, category: &str) -> Project {
+        Project::new(
+            name,
+            72,
+            category,
+            &format!("https://github.com/{}", name),
+            "",
+            "",
+            "",
+            "",
+        )
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.toggle(project("ncdu", "C")));
+        assert!(bookmarks.toggle(project("black", "Python")));
+        assert_eq!(
+            vec!["black", "ncdu"],
+            bookmarks
+                .page("", 1)
+                .0
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        assert!(!bookmarks.toggle(project("ncdu", "C")));
+        assert_eq!(1, bookmarks.page("", 1).0.len());
+    }
+
+    #[test]
+    fn test_page() {
+        let mut bookmarks = Bookmarks::default();
+        for i in 0..LOCAL_PAGE_SIZE + 5 {
+            bookmarks.toggle(project(&format!("rust-{}", i), "Rust"));
+        }
+        bookmarks.toggle(project("black", "Python"));
+
+        assert_eq!(2, bookmarks.page("", 1).1);
+        assert_eq!(LOCAL_PAGE_SIZE, bookmarks.page("", 1).0.len());
+        assert_eq!(6, bookmarks.page("", 2).0.len());
+        assert!(bookmarks.page("", 3).0.is_empty());
+
+        let (projects, pages) = bookmarks.page("PYTHON", 1);
+        assert_eq!(1, pages);
+        assert_eq!("black", projects[0].name);
+        assert_eq!((vec![], 0), bookmarks.page("golang", 1));
+    }
+
+    #[test]
+    fn test_keyword() {
+        assert_eq!("", keyword("@fav"));
+        assert_eq!("rust", keyword("@fav rust"));
+        assert_eq!("rust", keyword("@rust"));
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// 一小时
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// 页面类型，不同类型的页面缓存有效期不同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    /// 首页，用来获取最新期数
+    Home,
+
+    /// 月刊，往期内容不会再变
+    Volume { latest: bool },
+
+    /// 类别
+    Category,
+
+    /// 搜索结果
+    Search,
+
+    /// 项目的 README
+    Readme,
+}
+
+impl PageKind {
+    /// 缓存有效期，`None` 表示永不过期
+    pub fn ttl(&self) -> Option<Duration> {
+        match self {
+            PageKind::Home => Some(HOUR),
+            PageKind::Volume { latest: false } => None,
+            PageKind::Volume { latest: true } => Some(24 * HOUR),
+            PageKind::Category => Some(24 * HOUR),
+            PageKind::Search => Some(HOUR),
+            PageKind::Readme => Some(24 * HOUR),
+        }
+    }
+}
+
+/// 保存在磁盘上的页面缓存，一个 url 对应一个文件
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    dir: PathBuf,
+}
+
+impl PageCache {
+    pub fn new(dir: impl Into<PathBuf>) -> PageCache {
+        PageCache { dir: dir.into() }
+    }
+
+    /// 默认缓存目录，如 Linux 下的 `~/.cache/hgtui`
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("hgtui"))
+    }
+
+    /// 读取未过期的缓存
+    pub fn get(&self, url: &str, ttl: Option<Duration>) -> Option<String> {
+        let path = self.path(url);
+        if let Some(ttl) = ttl {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            let age = SystemTime::now()
+                .duration_since(modified)
+                .unwrap_or_default();
+            if age >= ttl {
+                return None;
+            }
+        }
+        fs::read_to_string(path).ok()
+    }
+
+    /// 读取缓存，不管是否过期
+    pub fn get_stale(&self, url: &str) -> Option<String> {
+        fs::read_to_string(self.path(url)).ok()
+    }
+
+    pub fn put(&self, url: &str, html: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(url), html)
+    }
+
+    /// 读取缓存的图片，图片地址对应的内容不会变，不用判断是否过期
+    pub fn get_image(&self, url: &str) -> Option<Vec<u8>> {
+        fs::read(self.image_path(url)).ok()
+    }
+
+    pub fn put_image(&self, url: &str, bytes: &[u8]) -> io::Result<()> {
+        let path = self.image_path(url);
+        fs::create_dir_all(path.parent().unwrap_or(&self.dir))?;
+        fs::write(path, bytes)
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.html", fnv1a(url)))
+    }
+
+    fn image_path(&self, url: &str) -> PathBuf {
+        self.dir.join("images").join(format!("{:016x}", fnv1a(url)))
+    }
+}
+
+/// 文件名用 url 的哈希，需要在不同版本之间保持稳定，所以不用 `DefaultHasher`
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_put_and_get() {
+        let dir = std::env::temp_dir().join(format!("hgtui-cache-{}", std::process::id()));
+        let cache = PageCache::new(&dir);
+        let url = "https://hellogithub.com/periodical/volume/72/";
+
+        assert!(cache.get(url, None).is_none());
+
+        cache.put(url, "<h1>第 72 期</h1>").unwrap();
+        assert_eq!(Some("<h1>第 72 期</h1>".to_string()), cache.get(url, None));
+        assert_eq!(
+            Some("<h1>第 72 期</h1>".to_string()),
+            cache.get(url, Some(HOUR))
+        );
+        // 已过期
+        assert!(cache.get(url, Some(Duration::ZERO)).is_none());
+        assert!(cache.get_stale(url).is_some());
+
+        let image = "https://img.hellogithub.com/i/ncdu.png";
+        assert!(cache.get_image(image).is_none());
+        cache.put_image(image, &[0x89, b'P', b'N', b'G']).unwrap();
+        assert_eq!(Some(vec![0x89, b'P', b'N', b'G']), cache.get_image(image));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ttl() {
+        assert_eq!(None, PageKind::Volume { latest: false }.ttl());
+        assert!(PageKind::Volume { latest: true }.ttl().is_some());
+        assert!(PageKind::Search.ttl() < PageKind::Category.ttl());
+    }
+}
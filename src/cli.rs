@@ -1,6 +1,6 @@
-use clap::Parser;
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand};
 
-use crate::{config::Config, theme::Theme};
+use crate::{config::Config, export::Format, theme::Theme};
 use anyhow::Result;
 
 #[derive(Parser, Debug)]
@@ -11,7 +11,7 @@ use anyhow::Result;
     long_about = "A TUI toolkit to view HelloGitHub"
 )]
 pub struct Args {
-    #[clap(short, long, help = "配置文件路径")]
+    #[clap(short, long, help = "配置文件路径，默认为 ~/.hgtui.toml")]
     pub path: Option<String>,
 
     #[clap(short, long, help = "是否显示帮助")]
@@ -20,16 +20,103 @@ pub struct Args {
     #[clap(
         short,
         long,
-        help = "终端样式，使用 --show-themes 查看内置样式列表",
-        default_value = "darkcolorful"
+        help = "终端样式，默认 darkcolorful，使用 --show-themes 查看内置样式列表"
     )]
-    pub color_theme: Theme,
+    pub color_theme: Option<Theme>,
 
     #[clap(long, help = "显示内置样式列表")]
     pub show_themes: bool,
+
+    #[clap(long, help = "启动后默认执行的搜索，如 #72、$rust、python")]
+    pub startup: Option<String>,
+
+    #[clap(long, help = "浏览模式下 u/d 移动的行数")]
+    pub page_size: Option<usize>,
+
+    #[clap(long, help = "请求超时时间（秒）")]
+    pub timeout: Option<u64>,
+
+    #[clap(long, help = "HelloGitHub 站点地址，可以指向本地镜像")]
+    pub base_url: Option<String>,
+
+    #[clap(long, help = "代理地址，如 http://127.0.0.1:7890")]
+    pub proxy: Option<String>,
+
+    #[clap(long, help = "离线模式，只浏览本地缓存过的页面")]
+    pub offline: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 不进入界面，直接在终端输出结果
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// 检查 HelloGitHub 的页面结构是否还能正常解析
+    Doctor,
+
+    /// 输出某一期的项目，不填期数时输出最新一期
+    Volume {
+        #[clap(value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        volume: Option<usize>,
+
+        #[clap(long, help = "输出格式：json、csv、markdown，默认为表格")]
+        format: Option<Format>,
+    },
+
+    /// 输出某个类别的项目
+    Category {
+        /// 类别名称，如 java、py、js、go
+        category: String,
+
+        #[clap(
+            long,
+            default_value_t = 1,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+            help = "页数"
+        )]
+        page: usize,
+
+        #[clap(long, help = "输出格式：json、csv、markdown，默认为表格")]
+        format: Option<Format>,
+    },
+
+    /// 按关键字搜索项目
+    Search {
+        keyword: String,
+
+        #[clap(long, help = "输出格式：json、csv、markdown，默认为表格")]
+        format: Option<Format>,
+    },
+
+    /// 输出 HelloGitHub 的最新期数、项目数
+    Info,
+
+    /// 把所有月刊同步到本地索引，之后关键字搜索不再需要联网
+    Sync {
+        #[clap(long, help = "重新同步所有月刊，默认只同步新的一期")]
+        full: bool,
+    },
 }
 
 pub fn parse_args() -> Result<Config> {
     let args = Args::parse();
-    Ok(Config::from(args))
+    Config::load(args)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_page_range() {
+        let args = Args::parse_from(["hgtui", "category", "rust", "--page", "2"]);
+        assert!(matches!(
+            args.command,
+            Some(Command::Category { page: 2, .. })
+        ));
+
+        assert!(Args::try_parse_from(["hgtui", "category", "rust", "--page", "0"]).is_err());
+        assert!(Args::try_parse_from(["hgtui", "volume", "0"]).is_err());
+    }
 }
@@ -0,0 +1,283 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::SearchMode;
+use crate::cli::Command;
+use crate::config::{CacheConfig, Config, IndexConfig};
+use crate::error;
+use crate::export::{self, Format};
+use crate::fetch;
+use crate::index::{self, IndexFile};
+use crate::parse::{CategoryParser, NormalParser, Parser, VolumeParser};
+use crate::utils::pad;
+use crate::widget::content::{Category, Project};
+use crate::worker::{self, Job};
+
+pub fn run(command: &Command, config: &Config) -> Result<()> {
+    match command {
+        Command::Doctor => {
+            // 检查的是线上（或镜像）页面，不能读缓存
+            let cache = CacheConfig {
+                enabled: false,
+                ..config.cache.clone()
+            };
+            fetch::init(&config.network, &cache)?;
+            doctor()
+        }
+        Command::Volume { volume, format } => {
+            fetch::init(&config.network, &config.cache)?;
+            let job = match volume {
+                Some(volume) => Job::Search(format!("#{}", volume), SearchMode::Volume),
+                None => Job::Latest,
+            };
+            print_projects(&job, *format)
+        }
+        Command::Category {
+            category,
+            page,
+            format,
+        } => {
+            fetch::init(&config.network, &config.cache)?;
+            let category = Category::try_from(category.clone())?;
+            print_projects(
+                &Job::Page(SearchMode::Category, Some(category), *page),
+                *format,
+            )
+        }
+        Command::Search { keyword, format } => {
+            fetch::init(&config.network, &config.cache)?;
+            index::init(&config.index, &config.data_dir)?;
+            print_projects(&Job::Search(keyword.clone(), SearchMode::Normal), *format)
+        }
+        Command::Info => {
+            fetch::init(&config.network, &config.cache)?;
+            let info = fetch::hg_info()?;
+            println!("最新期数 {}", info.max_volume);
+            println!("项目数 {}", info.project_count);
+            println!("Star {}", info.star);
+            Ok(())
+        }
+        Command::Sync { full } => {
+            fetch::init(&config.network, &config.cache)?;
+            sync(&config.index, &config.data_dir, *full)
+        }
+    }
+}
+
+/// 输出项目，不指定格式时按列对齐输出，介绍放在最后一列，方便 grep、awk 处理
+fn print_projects(job: &Job, format: Option<Format>) -> Result<()> {
+    let (projects, last_parse) = worker::run(job)?;
+    match format {
+        Some(format) => print!("{}", export::export(&projects, &last_parse, format)?),
+        None => print!("{}", format_projects(&projects)),
+    }
+    Ok(())
+}
+
+fn format_projects(projects: &[Project]) -> String {
+    let header = ["名称", "期数", "分类", "Star", "地址", "介绍"];
+    let rows: Vec<[String; 6]> = projects
+        .iter()
+        .map(|project| {
+            [
+                project.name.clone(),
+                project.volume.to_string(),
+                project.category.clone(),
+                project.star.clone(),
+                project.url.clone(),
+                project.desc.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(|h| h.width());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let mut text = String::new();
+    let header = header.map(String::from);
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let (desc, cells) = row.split_last().unwrap();
+        for (cell, width) in cells.iter().zip(widths.iter()) {
+            text.push_str(&pad(cell, *width));
+            text.push_str("  ");
+        }
+        text.push_str(desc);
+        text.push('\n');
+    }
+    text
+}
+
+/// 逐期抓取月刊写入本地索引，已经同步过的往期不再请求，最新一期每次都重新同步
+fn sync(config: &IndexConfig, data_dir: &Path, full: bool) -> Result<()> {
+    let path = index::index_path(config, data_dir);
+    let mut file = if !full && path.exists() {
+        IndexFile::load(&path)?
+    } else {
+        IndexFile::default()
+    };
+
+    let max_volume = fetch::hg_info()?.max_volume;
+    let synced = file.volumes();
+    let mut failed = Vec::new();
+    for volume in 1..=max_volume {
+        if synced.contains(&volume) && volume != max_volume {
+            continue;
+        }
+        eprint!("\r同步第 {}/{} 期", volume, max_volume);
+        let result = fetch::fetch_volume(volume).and_then(|html| VolumeParser.parse(html));
+        match result {
+            Ok((projects, _)) => file.replace_volume(volume, projects),
+            Err(e) => failed.push((volume, e)),
+        }
+    }
+    eprintln!();
+
+    file.save(&path)?;
+    println!(
+        "同步完成，共 {} 期 {} 个项目，索引文件：{}",
+        file.volumes().len(),
+        file.projects.len(),
+        path.display()
+    );
+
+    if !failed.is_empty() {
+        for (volume, e) in &failed {
+            eprintln!("第 {} 期同步失败：{}", volume, e);
+        }
+        bail!(
+            "{} 期同步失败，可以稍后重新执行 hgtui sync，只会同步失败的部分",
+            failed.len()
+        );
+    }
+    Ok(())
+}
+
+/// 一项检查：页面、地址、结果
+type Check = (&'static str, String, Result<String, String>);
+
+/// 用每个解析器解析一遍线上页面，输出检查结果
+fn doctor() -> Result<()> {
+    let base_url = fetch::base_url();
+    let mut checks: Vec<Check> = Vec::new();
+
+    let info = fetch::fetch_hg_info();
+    checks.push((
+        "首页",
+        base_url.to_string(),
+        info.as_ref()
+            .map(|info| {
+                format!(
+                    "最新第 {} 期，共 {} 个项目",
+                    info.max_volume, info.project_count
+                )
+            })
+            .map_err(|e| e.to_string()),
+    ));
+
+    let volume = info.map_or(1, |info| info.max_volume);
+    checks.push((
+        "月刊",
+        fetch::volume_url(base_url, volume),
+        check(fetch::fetch_volume(volume), &VolumeParser),
+    ));
+    checks.push((
+        "类别",
+        fetch::category_url(base_url, Category::Python, 1),
+        check(fetch::fetch_category(Category::Python, 1), &CategoryParser),
+    ));
+    checks.push((
+        "搜索",
+        fetch::search_url(base_url, "python"),
+        check(fetch::search("python".to_string()), &NormalParser),
+    ));
+
+    let url_width = checks
+        .iter()
+        .map(|(_, url, _)| url.width())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{} {} {} 说明",
+        pad("页面", 4),
+        pad("结果", 7),
+        pad("地址", url_width)
+    );
+    for (page, url, result) in &checks {
+        let (status, detail) = match result {
+            Ok(detail) => ("✔ 通过", detail),
+            Err(e) => ("✘ 失败", e),
+        };
+        println!(
+            "{} {} {} {}",
+            pad(page, 4),
+            pad(status, 7),
+            pad(url, url_width),
+            detail
+        );
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|(_, _, result)| result.is_err())
+        .count();
+    if failed > 0 {
+        bail!("{} 项检查未通过", failed);
+    }
+    Ok(())
+}
+
+fn check(html: error::Result<String>, parser: &dyn Parser) -> Result<String, String> {
+    let html = html.map_err(|e| e.to_string())?;
+    let (projects, _) = parser.parse(html).map_err(|e| e.to_string())?;
+    if projects.is_empty() {
+        return Err("没有解析出任何项目".into());
+    }
+    Ok(format!("解析出 {} 个项目", projects.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_projects() {
+        let projects = vec![
+            Project::new(
+                "ncdu",
+                72,
+                "C",
+                "https://github.com/rofl0r/ncdu",
+                "命令行磁盘空间分析工具。",
+                "1.1k",
+                "30",
+                "80",
+            ),
+            Project::new(
+                "Python-100-Days",
+                30,
+                "开源书籍",
+                "https://github.com/jackfrued/Python-100-Days",
+                "Python 从新手到大师的 100 天学习计划。",
+                "120k",
+                "N/A",
+                "N/A",
+            ),
+        ];
+
+        let text = format_projects(&projects);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("名称             期数  分类      Star  地址"));
+        assert!(lines[1].starts_with(
+            "ncdu             72    C         1.1k  https://github.com/rofl0r/ncdu  "
+        ));
+        assert!(lines[1].ends_with("命令行磁盘空间分析工具。"));
+        assert_eq!(Some("Python-100-Days"), lines[2].split_whitespace().next());
+    }
+}
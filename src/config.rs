@@ -1,27 +1,397 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use toml_edit::{value, DocumentMut};
+
+use crate::cli::{Args, Command};
+use crate::draw::DetailLayout;
+use crate::export::Format;
+use crate::fetch::DEFAULT_BASE_URL;
+use crate::keymap::Keys;
+use crate::screenshot::Protocol;
 use crate::theme::Theme;
 
+/// 配置文件名
+const CONFIG_FILE: &str = ".hgtui.toml";
+
+/// 浏览模式下 u/d 默认移动的行数
+const DEFAULT_PAGE_SIZE: usize = 5;
+
+/// 默认请求超时时间（秒）
+const DEFAULT_TIMEOUT: u64 = 10;
+
 #[allow(dead_code)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct Config {
-    pub config_path: String,
+    /// 配置文件路径
+    pub config_path: PathBuf,
+
+    /// 是否要显示帮助
     pub show_help: bool,
+
+    /// 配色方案
     pub color_theme: Theme,
+
+    /// 是否只打印内置样式列表
     pub show_themes: bool,
+
+    /// 启动后默认执行的搜索，为空时展示最新一期
+    pub startup: Option<String>,
+
+    /// 浏览模式下 u/d 移动的行数
+    pub page_size: usize,
+
+    /// 是否开启鼠标，关闭后可以用终端自带的选择复制
+    pub mouse: bool,
+
+    /// 详情页的布局
+    pub layout: DetailLayout,
+
+    /// 自定义快捷键，动作名称 => 按键
+    pub keybindings: HashMap<String, Keys>,
+
+    /// 数据目录，保存本地索引、收藏等
+    pub data_dir: PathBuf,
+
+    /// 网络设置
+    pub network: NetworkConfig,
+
+    /// 缓存设置
+    pub cache: CacheConfig,
+
+    /// 导出设置
+    pub export: ExportConfig,
+
+    /// 本地索引设置
+    pub index: IndexConfig,
+
+    /// 截图设置
+    pub image: ImageConfig,
+
+    /// 子命令，有子命令时不进入界面
+    pub command: Option<Command>,
+}
+
+/// 网络设置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// HelloGitHub 站点地址，可以指向本地镜像
+    pub base_url: String,
+
+    /// 请求超时时间（秒）
+    pub timeout: u64,
+
+    /// 代理地址，如 http://127.0.0.1:7890
+    pub proxy: Option<String>,
+
+    /// 自定义 User-Agent
+    pub user_agent: Option<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            proxy: None,
+            user_agent: None,
+        }
+    }
+}
+
+/// 缓存设置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// 是否把页面缓存到本地
+    pub enabled: bool,
+
+    /// 缓存目录，默认为系统缓存目录下的 hgtui
+    pub dir: Option<PathBuf>,
+
+    /// 离线模式，只从缓存中读取
+    pub offline: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            enabled: true,
+            dir: None,
+            offline: false,
+        }
+    }
+}
+
+/// 导出设置
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// 浏览模式下按 e 导出的格式
+    pub format: Format,
+
+    /// 导出目录，默认为当前目录
+    pub dir: Option<PathBuf>,
+}
+
+/// 本地索引设置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IndexConfig {
+    /// 执行过 `hgtui sync` 后，关键字搜索是否使用本地索引
+    pub enabled: bool,
+
+    /// 索引文件路径，默认为数据目录下的 index.json
+    pub path: Option<PathBuf>,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        IndexConfig {
+            enabled: true,
+            path: None,
+        }
+    }
+}
+
+/// 截图设置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    /// 详情页是否展示项目截图
+    pub enabled: bool,
+
+    /// 显示方式：auto、halfblocks、kitty、sixel，auto 根据终端自动选择
+    pub protocol: Protocol,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        ImageConfig {
+            enabled: true,
+            protocol: Protocol::Auto,
+        }
+    }
 }
 
-impl From<crate::cli::Args> for Config {
-    fn from(args: crate::cli::Args) -> Self {
-        #[cfg(not(target_os = "windows"))]
-        let home = env!("HOME").to_string();
-        #[cfg(target_os = "windows")]
-        let home = env!("HOMEPATH").to_string();
+/// `.hgtui.toml` 的内容，所有字段都是可选的
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FileConfig {
+    theme: Option<String>,
+    startup: Option<String>,
+    page_size: Option<usize>,
+    mouse: Option<bool>,
+    layout: Option<DetailLayout>,
+    data_dir: Option<PathBuf>,
+    keybindings: HashMap<String, Keys>,
+    network: NetworkConfig,
+    cache: CacheConfig,
+    export: ExportConfig,
+    index: IndexConfig,
+    image: ImageConfig,
+}
+
+impl Config {
+    /// 读取配置文件，并用命令行参数覆盖其中的配置
+    pub fn load(args: Args) -> Result<Config> {
+        let config_path = resolve_path(args.path.as_deref());
+
+        // 配置文件不存在说明是第一次使用，创建一个空文件并展示帮助
+        let first_run = !config_path.exists() && File::create(&config_path).is_ok();
 
-        let config_path = args.path.unwrap_or(home);
-        Config {
+        let file_config = if config_path.exists() {
+            let text = fs::read_to_string(&config_path)
+                .with_context(|| format!("无法读取配置文件 {}", config_path.display()))?;
+            toml::from_str::<FileConfig>(&text)
+                .with_context(|| format!("配置文件 {} 格式有误", config_path.display()))?
+        } else {
+            FileConfig::default()
+        };
+
+        let color_theme = match (args.color_theme, file_config.theme) {
+            (Some(theme), _) => theme,
+            (None, Some(theme)) => theme.parse()?,
+            (None, None) => Theme::default(),
+        };
+
+        let mut network = file_config.network;
+        if let Some(timeout) = args.timeout {
+            network.timeout = timeout;
+        }
+        if args.proxy.is_some() {
+            network.proxy = args.proxy;
+        }
+        if let Some(base_url) = args.base_url {
+            network.base_url = base_url;
+        }
+
+        let mut cache = file_config.cache;
+        if args.offline {
+            cache.offline = true;
+        }
+        if cache.offline && !cache.enabled {
+            bail!("离线模式需要开启缓存，请检查配置文件中的 cache.enabled");
+        }
+
+        Ok(Config {
             config_path,
-            show_help: args.show_help,
-            color_theme: args.color_theme,
+            show_help: args.show_help || first_run,
+            color_theme,
             show_themes: args.show_themes,
+            startup: args
+                .startup
+                .or(file_config.startup)
+                .filter(|s| !s.trim().is_empty()),
+            page_size: args
+                .page_size
+                .or(file_config.page_size)
+                .filter(|size| *size > 0)
+                .unwrap_or(DEFAULT_PAGE_SIZE),
+            mouse: file_config.mouse.unwrap_or(true),
+            layout: file_config.layout.unwrap_or_default(),
+            keybindings: file_config.keybindings,
+            data_dir: file_config
+                .data_dir
+                .or_else(|| dirs::data_dir().map(|dir| dir.join("hgtui")))
+                .unwrap_or_else(|| home_dir().join(".hgtui")),
+            network,
+            cache,
+            export: file_config.export,
+            index: file_config.index,
+            image: file_config.image,
+            command: args.command,
+        })
+    }
+}
+
+/// 把布局写回配置文件，保留文件里原有的注释和格式
+pub fn save_layout(config_path: &Path, layout: DetailLayout) -> Result<()> {
+    let text = match fs::read_to_string(config_path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("无法读取配置文件 {}", config_path.display()))
         }
+    };
+    let mut document = text
+        .parse::<DocumentMut>()
+        .with_context(|| format!("配置文件 {} 格式有误", config_path.display()))?;
+    document["layout"] = value(layout.name());
+    fs::write(config_path, document.to_string())
+        .with_context(|| format!("无法保存配置文件 {}", config_path.display()))
+}
+
+/// 命令行可以传目录也可以传文件，默认是用户目录下的 `.hgtui.toml`
+fn resolve_path(path: Option<&str>) -> PathBuf {
+    match path {
+        Some(path) if Path::new(path).is_dir() => Path::new(path).join(CONFIG_FILE),
+        Some(path) => PathBuf::from(path),
+        None => home_dir().join(CONFIG_FILE),
+    }
+}
+
+fn home_dir() -> PathBuf {
+    #[cfg(not(target_os = "windows"))]
+    let home = std::env::var("HOME");
+    #[cfg(target_os = "windows")]
+    let home = std::env::var("USERPROFILE");
+
+    home.map(PathBuf::from).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_config() {
+        let text = r#"
+theme = "highcontrast"
+startup = "$rust"
+page_size = 10
+mouse = false
+layout = "side"
+
+[keybindings]
+next_row = "n"
+first_row = ["g g", "ctrl-x ctrl-a"]
+
+[network]
+base_url = "http://127.0.0.1:8000"
+timeout = 3
+proxy = "http://127.0.0.1:7890"
+
+[cache]
+offline = true
+
+[export]
+format = "json"
+dir = "/tmp"
+
+[image]
+protocol = "halfblocks"
+"#;
+        let config: FileConfig = toml::from_str(text).unwrap();
+        assert_eq!(Some("highcontrast".to_string()), config.theme);
+        assert_eq!(Some("$rust".to_string()), config.startup);
+        assert_eq!(Some(10), config.page_size);
+        assert_eq!(Some(false), config.mouse);
+        assert_eq!(Some(DetailLayout::Side), config.layout);
+        assert_eq!(Keys::One("n".into()), config.keybindings["next_row"]);
+        assert_eq!(
+            Keys::Many(vec!["g g".into(), "ctrl-x ctrl-a".into()]),
+            config.keybindings["first_row"]
+        );
+        assert_eq!("http://127.0.0.1:8000", config.network.base_url);
+        assert_eq!(3, config.network.timeout);
+        assert_eq!(None, config.network.user_agent);
+        assert!(config.cache.enabled);
+        assert!(config.cache.offline);
+        assert_eq!(Format::Json, config.export.format);
+        assert_eq!(Some(PathBuf::from("/tmp")), config.export.dir);
+        assert!(config.image.enabled);
+        assert_eq!(Protocol::HalfBlocks, config.image.protocol);
+    }
+
+    #[test]
+    fn test_parse_empty_file_config() {
+        let config: FileConfig = toml::from_str("").unwrap();
+        assert!(config.theme.is_none());
+        assert_eq!(DEFAULT_BASE_URL, config.network.base_url);
+        assert_eq!(DEFAULT_TIMEOUT, config.network.timeout);
+        assert_eq!(Format::Markdown, config.export.format);
+        assert!(config.index.enabled);
+        assert_eq!(Protocol::Auto, config.image.protocol);
+        assert!(config.layout.is_none());
+    }
+
+    #[test]
+    fn test_save_layout() {
+        let path = std::env::temp_dir().join(format!("hgtui-config-{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        save_layout(&path, DetailLayout::Stacked).unwrap();
+        assert_eq!("layout = \"stacked\"\n", fs::read_to_string(&path).unwrap());
+
+        fs::write(
+            &path,
+            "# 主题\ntheme = \"dark\"\n\n[cache]\nenabled = false\n",
+        )
+        .unwrap();
+        save_layout(&path, DetailLayout::Side).unwrap();
+        save_layout(&path, DetailLayout::Stacked).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("# 主题\ntheme = \"dark\"\n"));
+        let config: FileConfig = toml::from_str(&text).unwrap();
+        assert_eq!(Some(DetailLayout::Stacked), config.layout);
+        assert!(!config.cache.enabled);
+
+        fs::remove_file(&path).unwrap();
     }
 }
@@ -1,25 +1,119 @@
 use crate::app::{App, AppMode};
 use crate::app_global::THEME;
 use crate::theme::ThemeStyle;
+use crate::widget::completion::Completion;
+use crate::widget::editor::Editor;
 use crate::widget::projectdetail::ProjectDetail;
 use crate::widget::{Content, Input, Popup, StatusLine};
 
+use std::io;
+
+use serde::Deserialize;
+
 use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 
 use tui::text::Text;
-use tui::widgets::{Block, Paragraph};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph};
+
+/// 终端宽度小于这个值时左右分栏改成上下分栏
+const MIN_SIDE_WIDTH: u16 = 120;
+
+/// 详情页的布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DetailLayout {
+    /// 详情页盖在列表上面，按 o 打开
+    #[default]
+    Overlay,
+
+    /// 列表在左，选中项目的详情在右
+    Side,
+
+    /// 列表在上，选中项目的详情在下
+    Stacked,
+}
+
+impl DetailLayout {
+    pub fn next(self) -> DetailLayout {
+        match self {
+            DetailLayout::Overlay => DetailLayout::Side,
+            DetailLayout::Side => DetailLayout::Stacked,
+            DetailLayout::Stacked => DetailLayout::Overlay,
+        }
+    }
+
+    /// 配置文件里的写法
+    pub fn name(self) -> &'static str {
+        match self {
+            DetailLayout::Overlay => "overlay",
+            DetailLayout::Side => "side",
+            DetailLayout::Stacked => "stacked",
+        }
+    }
+
+    /// 终端太窄时左右分栏放不下，改成上下分栏
+    fn resolve(self, width: u16) -> DetailLayout {
+        match self {
+            DetailLayout::Side if width < MIN_SIDE_WIDTH => DetailLayout::Stacked,
+            layout => layout,
+        }
+    }
+}
 
 pub fn redraw(app: &mut App) {
+    app.sync_preview();
+    let detail_shown = draw(app);
+    // sixel 图片只能靠整屏重绘擦掉
+    if show_image(app, detail_shown) {
+        app.terminal.clear().unwrap();
+        draw(app);
+        show_image(app, detail_shown);
+    }
+}
+
+/// kitty、sixel 的截图不归 tui 管，画完之后再输出，返回是否需要整屏重绘
+fn show_image(app: &mut App, detail_shown: bool) -> bool {
+    let graphics = match &mut app.graphics {
+        Some(graphics) => graphics,
+        None => return false,
+    };
+    // 弹窗、补全、编辑笔记会盖住截图，这时不显示
+    let image = match app.mode {
+        AppMode::Detail | AppMode::View if detail_shown => app.project_detail.placement(),
+        _ => None,
+    };
+    // 输出失败时不显示截图，不影响其他操作
+    graphics.show(&mut io::stdout(), image).unwrap_or(false)
+}
+
+/// 返回这次是否画了详情
+fn draw(app: &mut App) -> bool {
     let terminal = &mut app.terminal;
+    let mut detail_shown = false;
 
     terminal
         .draw(|f| {
             let theme_style = THEME.get().unwrap();
             f.render_widget(Block::default().style(theme_style.background), f.size());
 
-            if app.mode == AppMode::Detail {
-                let area = centered_rect(80, 50, f.size());
-                f.render_stateful_widget(ProjectDetail {}, area, &mut app.project_detail);
+            let layout_kind = app.layout.resolve(f.size().width);
+            let detail = ProjectDetail {
+                graphics: app.graphics.is_some(),
+            };
+
+            if layout_kind == DetailLayout::Overlay
+                && matches!(app.mode, AppMode::Detail | AppMode::Edit)
+            {
+                // 有截图或者 README 时详情页高一些
+                let percent_y =
+                    if app.project_detail.has_image() || app.project_detail.is_showing_readme() {
+                        80
+                    } else {
+                        50
+                    };
+                let area = centered_rect(80, percent_y, f.size());
+                f.render_stateful_widget(detail, area, &mut app.project_detail);
+                detail_shown = true;
             } else {
                 // layout[0] => title
                 // layout[1] => input
@@ -54,15 +148,59 @@ pub fn redraw(app: &mut App) {
 
                 f.render_stateful_widget(Input {}, input_layout, &mut app.input);
                 if let AppMode::Search = app.mode {
-                    f.set_cursor(
-                        input_layout.x + app.input.width() as u16 + 1,
-                        input_layout.y + 1,
-                    )
+                    f.set_cursor(input_layout.x + app.input.cursor() + 1, input_layout.y + 1)
                 }
 
-                f.render_stateful_widget(Content {}, layout[2], &mut app.content);
+                // 分栏时搜索结果和选中项目的详情各占一半
+                let content_area = match layout_kind {
+                    DetailLayout::Overlay => layout[2],
+                    DetailLayout::Side | DetailLayout::Stacked => {
+                        let (direction, constraints) = if layout_kind == DetailLayout::Side {
+                            (Direction::Horizontal, [Constraint::Percentage(50); 2])
+                        } else {
+                            (
+                                Direction::Vertical,
+                                [Constraint::Percentage(40), Constraint::Percentage(60)],
+                            )
+                        };
+                        let panes = Layout::default()
+                            .direction(direction)
+                            .constraints(constraints.as_ref())
+                            .split(layout[2]);
+                        if !app.project_detail.url().is_empty() {
+                            f.render_stateful_widget(detail, panes[1], &mut app.project_detail);
+                            detail_shown = true;
+                        }
+                        panes[0]
+                    }
+                };
+
+                f.render_stateful_widget(
+                    Content {
+                        seen: app.seen.get(),
+                    },
+                    content_area,
+                    &mut app.content,
+                );
 
                 f.render_stateful_widget(StatusLine {}, layout[3], &mut app.statusline);
+
+                // 补全弹窗，盖在搜索结果上方
+                if app.mode == AppMode::Search
+                    && !app.input.is_reverse_searching()
+                    && !app.completion.is_empty()
+                {
+                    let area = Rect {
+                        y: input_layout.y + input_layout.height,
+                        height: app
+                            .completion
+                            .height()
+                            .min(layout[2].bottom().saturating_sub(layout[2].y)),
+                        ..input_layout
+                    };
+                    f.render_stateful_widget(Completion {}, area, &mut app.completion);
+                }
+
                 // popup
                 if app.mode == AppMode::Popup {
                     let area = centered_rect(50, 50, f.size());
@@ -70,8 +208,39 @@ pub fn redraw(app: &mut App) {
                     f.render_stateful_widget(Popup {}, area, &mut app.popup);
                 }
             }
+
+            if app.mode == AppMode::Edit {
+                let area = centered_rect(60, 50, f.size());
+                f.render_widget(Clear, area);
+                f.render_widget(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .title(" 编辑笔记  Tab 切换  Ctrl+S 保存  Esc 取消 ")
+                        .title_alignment(Alignment::Center)
+                        .style(theme_style.background),
+                    area,
+                );
+                let layout = Layout::default()
+                    .margin(1)
+                    .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+                    .split(area);
+
+                let title = " 🏷 标签（空格分隔） ";
+                f.render_stateful_widget(Editor { title }, layout[0], &mut app.tags_editor);
+                let title = " 📝 笔记 ";
+                f.render_stateful_widget(Editor { title }, layout[1], &mut app.note_editor);
+
+                let (editor_area, (x, y)) = if app.tags_editor.is_active() {
+                    (layout[0], app.tags_editor.cursor())
+                } else {
+                    (layout[1], app.note_editor.cursor())
+                };
+                f.set_cursor(editor_area.x + 1 + x, editor_area.y + 1 + y);
+            }
         })
         .unwrap();
+    detail_shown
 }
 
 fn title(theme_style: &ThemeStyle) -> Paragraph<'static> {
@@ -113,3 +282,24 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         )
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detail_layout() {
+        let mut layout = DetailLayout::default();
+        let mut names = vec![];
+        for _ in 0..3 {
+            names.push(layout.name());
+            layout = layout.next();
+        }
+        assert_eq!(vec!["overlay", "side", "stacked"], names);
+        assert_eq!(DetailLayout::Overlay, layout);
+
+        assert_eq!(DetailLayout::Side, DetailLayout::Side.resolve(160));
+        assert_eq!(DetailLayout::Stacked, DetailLayout::Side.resolve(100));
+        assert_eq!(DetailLayout::Overlay, DetailLayout::Overlay.resolve(100));
+    }
+}
@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+/// 请求、解析 HelloGitHub 页面时可能出现的错误
+#[derive(Debug, Error)]
+pub enum HgError {
+    /// 网络不通、超时等
+    #[error("网络请求失败：{0}")]
+    Network(#[from] reqwest::Error),
+
+    /// 服务端返回了非 2xx 的状态码
+    #[error("请求 {url} 失败，状态码：{status}")]
+    Status { url: String, status: u16 },
+
+    /// 离线模式下没有缓存
+    #[error("离线模式下没有找到缓存：{0}")]
+    Offline(String),
+
+    /// 页面内容不符合预期，如期数不是数字
+    #[error("解析失败：{0}")]
+    Parse(String),
+
+    /// 不是 GitHub 上的项目，不知道去哪里找 README
+    #[error("暂时只支持查看 GitHub 项目的 README：{0}")]
+    Unsupported(String),
+
+    /// 项目里没有常见文件名的 README
+    #[error("{0} 没有 README")]
+    NoReadme(String),
+
+    /// 页面结构变了，找不到需要的元素
+    #[error("{page}页面结构发生了变化，找不到 `{selector}`，请升级 hg-tui 或者提 issue")]
+    LayoutChanged {
+        page: &'static str,
+        selector: String,
+    },
+}
+
+impl HgError {
+    pub fn layout_changed(page: &'static str, selector: impl Into<String>) -> HgError {
+        HgError::LayoutChanged {
+            page,
+            selector: selector.into(),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, HgError>;
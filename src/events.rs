@@ -1,25 +1,35 @@
 use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 
+use image::DynamicImage;
 use lazy_static::lazy_static;
+use tui::layout::Rect;
 
 use crate::app::{App, AppMode};
-use crate::app_global::HG_INFO;
 use crate::draw;
+use crate::keymap::{Action, Resolved, Scope};
+use crate::terminal;
+use crate::widget::scrollbar::ScrollState;
+use crate::widget::statusline::Arrow;
+use crate::worker::{Job, JobResult};
 
-use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 lazy_static! {
     pub static ref NOTIFY: (Sender<HGEvent>, Receiver<HGEvent>) = bounded(1024);
-    pub static ref GG_COMBINE: AtomicBool = AtomicBool::new(false);
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum HGEvent {
     UserEvent(KeyEvent),
 
+    /// 鼠标点击和滚轮，移动不会发过来
+    MouseEvent(MouseEvent),
+
     NotifyEvent(Notify),
 }
 
@@ -36,6 +46,18 @@ pub enum Notify {
 
     /// tick
     Tick,
+
+    /// 后台请求完成，请求编号、请求内容和结果
+    Fetched(u64, Job, JobResult),
+
+    /// 截图加载完成，截图地址和结果
+    Image(String, Result<Arc<DynamicImage>, String>),
+
+    /// README 下载完成，项目地址和结果
+    Readme(String, Result<String, String>),
+
+    /// kitty、sixel 的截图编码完成，截图地址、显示区域和要输出的内容
+    Graphics(String, Rect, Vec<u8>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -55,63 +77,162 @@ impl Default for Message {
 
 pub fn handle_key_event(event_app: Arc<Mutex<App>>) {
     let (sender, receiver) = unbounded();
-    sender
-        .send(HGEvent::UserEvent(KeyEvent {
-            code: KeyCode::Char('#'),
-            modifiers: KeyModifiers::NONE,
-        }))
-        .unwrap();
-    let max_volume = HG_INFO.max_volume.to_string();
-
-    for c in max_volume.chars() {
-        sender
-            .send(HGEvent::UserEvent(KeyEvent {
-                code: KeyCode::Char(c),
-                modifiers: KeyModifiers::NONE,
-            }))
-            .unwrap();
-    }
-    sender
-        .send(HGEvent::UserEvent(KeyEvent {
-            code: KeyCode::Enter,
-            modifiers: KeyModifiers::NONE,
-        }))
-        .unwrap();
 
     std::thread::spawn(move || loop {
-        if let Ok(Event::Key(event)) = crossterm::event::read() {
-            sender.send(HGEvent::UserEvent(event)).unwrap();
+        // crossterm 0.23 没有 bracketed paste（Event::Paste），粘贴的内容会逐个按键到达
+        let event = match crossterm::event::read() {
+            Ok(Event::Key(event)) => Some(HGEvent::UserEvent(event)),
+            Ok(Event::Mouse(event)) => mouse_event(event),
+            _ => None,
+        };
+        if let Some(event) = event {
+            sender.send(event).unwrap();
         }
     });
     std::thread::spawn(move || loop {
-        if let Ok(HGEvent::UserEvent(key_event)) = receiver.recv() {
-            let mut app = event_app.lock().unwrap();
-            match (key_event.modifiers, key_event.code) {
-                (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
-                    quit();
-                    break;
-                }
-                (_, KeyCode::Char('q')) if app.mode != AppMode::Search => {
-                    quit();
-                    break;
+        let running = match receiver.recv() {
+            Ok(HGEvent::UserEvent(key_event)) => {
+                handle_key(key_event, &mut event_app.lock().unwrap())
+            }
+            Ok(HGEvent::MouseEvent(event)) => {
+                handle_mouse(event, &mut event_app.lock().unwrap());
+                true
+            }
+            _ => true,
+        };
+        if !running {
+            break;
+        }
+    });
+}
+
+/// 只转发点击和滚轮，鼠标移动、拖动太频繁了，也用不上
+fn mouse_event(event: MouseEvent) -> Option<HGEvent> {
+    match event.kind {
+        MouseEventKind::Down(_) | MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            Some(HGEvent::MouseEvent(event))
+        }
+        _ => None,
+    }
+}
+
+/// 不带 Ctrl、Alt 的可见字符
+fn typed_char(event: &KeyEvent) -> Option<char> {
+    match (event.modifiers, event.code) {
+        (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => Some(c),
+        _ => None,
+    }
+}
+
+/// 处理一次按键，返回是否继续运行
+fn handle_key(key_event: KeyEvent, app: &mut App) -> bool {
+    let (modifiers, code) = (key_event.modifiers, key_event.code);
+    if (modifiers, code) == (KeyModifiers::CONTROL, KeyCode::Char('c')) {
+        quit();
+        return false;
+    }
+    let scope = match app.mode {
+        AppMode::Search if app.input.is_reverse_searching() => {
+            handle_reverse_search(modifiers, code, app);
+            app.completion.update(app.input.text());
+            return true;
+        }
+        AppMode::Search => Scope::Search,
+        AppMode::View => Scope::View,
+        AppMode::Detail => Scope::Detail,
+        AppMode::Popup if app.popup.scroll.is_scrollable() => Scope::Popup,
+        AppMode::Popup => {
+            handle_popup(app);
+            return true;
+        }
+        AppMode::Edit => {
+            handle_edit(modifiers, code, app);
+            return true;
+        }
+    };
+
+    let running = match app.keymap.feed(scope, (modifiers, code)) {
+        Resolved::Action(action, count) => handle_action(action, count, app),
+        Resolved::Pending => true,
+        Resolved::Unbound(_) => {
+            // 搜索模式下没有绑定动作的字符直接输入
+            if let (Scope::Search, Some(char)) = (scope, typed_char(&key_event)) {
+                let mode = app.input.handle_char(char);
+                app.statusline.set_mode(mode);
+                redraw();
+            }
+            // 可以滚动的弹窗里其他按键关闭弹窗
+            if scope == Scope::Popup {
+                handle_popup(app);
+            }
+            true
+        }
+    };
+    if scope == Scope::Search {
+        app.completion.update(app.input.text());
+    }
+    running
+}
+
+/// 单击选中行，双击打开详情，滚轮上下移动，点状态栏的箭头翻页，点击关闭弹窗
+fn handle_mouse(event: MouseEvent, app: &mut App) {
+    let (x, y) = (event.column, event.row);
+    match (app.mode, event.kind) {
+        (AppMode::Popup, MouseEventKind::Down(_)) => {
+            handle_popup(app);
+            return;
+        }
+        (AppMode::Search | AppMode::View, MouseEventKind::Down(MouseButton::Left)) => {
+            if let Some(row) = app.content.row_at(x, y) {
+                if app.mode == AppMode::Search {
+                    app.switch_to_view();
                 }
-                (key_modifier, key_code) => match app.mode {
-                    AppMode::Search => {
-                        handle_search(key_modifier, key_code, &mut app);
-                    }
-                    AppMode::View => {
-                        handle_view(key_modifier, key_code, &mut app);
-                    }
-                    AppMode::Popup => {
-                        handle_popup(key_modifier, key_code, &mut app);
+                if app.content.click(row, Instant::now()) {
+                    if let Err(e) = app.display_detail() {
+                        err(e.to_string());
                     }
-                    AppMode::Detail => {
-                        handle_detail(key_modifier, key_code, &mut app);
-                    }
-                },
+                }
+            } else if let Some(arrow) = app.statusline.arrow_at(x, y) {
+                match arrow {
+                    Arrow::Prev => app.prev_page(1),
+                    Arrow::Next => app.next_page(1),
+                }
+            } else {
+                return;
             }
         }
-    });
+        (
+            AppMode::Popup | AppMode::Detail,
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp,
+        ) => {
+            let state = match app.mode {
+                AppMode::Popup => &mut app.popup.scroll,
+                _ => app.project_detail.scroll_mut(),
+            };
+            if event.kind == MouseEventKind::ScrollDown {
+                state.down(1);
+            } else {
+                state.up(1);
+            }
+        }
+        (
+            AppMode::Search | AppMode::View,
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp,
+        ) if app.content.contains(x, y) => {
+            if app.mode == AppMode::Search {
+                app.switch_to_view();
+            }
+            if event.kind == MouseEventKind::ScrollDown {
+                app.content.next(1);
+            } else {
+                app.content.prev(1);
+            }
+        }
+        _ => return,
+    }
+    // 鼠标操作打断输入到一半的数字和组合键
+    app.keymap.reset();
+    redraw();
 }
 
 pub fn redraw() {
@@ -150,18 +271,34 @@ pub fn show_help() {
 Ctrl+h 获得帮助
 输入 #{数字} 按期数搜索
 输入 ${类别} 按类别搜索
+输入 @fav 查看收藏夹，@fav {关键字} 过滤收藏
+输入 %{标签} 按自己打的标签搜索
 其他按关键字搜索
+Tab 补全类别和期数，Up/Down 翻看搜索记录，Ctrl+r 搜索历史记录
+Left/Right Home/End Ctrl+a/e Alt+b/f 移动光标
+Ctrl+w 删除前一个词，Ctrl+u/k 删除光标前/后的内容
 
 浏览模式：
 k(Up)/j(Down) 上/下 移动一行
-u(PageUp)/d(PageDown) 上/下 移动五行
+u(PageUp)/d(PageDown) 上/下 移动多行
 gg(Home) 移动至首行
+H/M/L 移动至屏幕顶部/中间/底部，Ctrl+d/u 向下/上移动半屏
+:{数字} 跳转到某一期，如 :72
 G(End)  移动至末行
 h(Left)/l(Right) 前/后 翻页
 o | Ctrl+Right(Left) 查看（关闭）详细
+v 切换详情页布局：覆盖、左右分栏、上下分栏
+b 收藏/取消收藏
+m 切换已读/未读，n 跳到下一个未读，A 整期标记为已读
+e 导出当前结果
 s 帮 HG 点个小星星吧
 ENTER 打开 GitHub 页面
-q 退出应用"###
+详情页按 i 编辑笔记和标签，r 查看 README
+详情页、弹窗里 j/k、d/u、gg/G 滚动内容
+q 退出应用
+
+浏览模式下按键前可以输入数字，如 5j 向下移动 5 行，3l 往后翻 3 页，10G 移动到第 10 行
+快捷键可以在配置文件的 [keybindings] 中修改"###
             .into(),
     );
 }
@@ -170,131 +307,250 @@ pub fn tick() {
     NOTIFY.0.send(HGEvent::NotifyEvent(Notify::Tick)).unwrap();
 }
 
-/// 搜索模式
-fn handle_search(key_modifier: KeyModifiers, key_code: KeyCode, app: &mut App) {
-    match (key_modifier, key_code) {
-        (KeyModifiers::CONTROL, KeyCode::Char('j'))
-        | (KeyModifiers::CONTROL, KeyCode::Down)
-        | (_, KeyCode::Esc) => {
-            // switch to view
-            app.switch_to_view();
-            redraw();
+/// 执行按键对应的动作，`count` 是按键前输入的数字，返回是否继续运行
+fn handle_action(action: Action, count: Option<usize>, app: &mut App) -> bool {
+    let times = count.unwrap_or(1);
+    match action {
+        Action::Quit => {
+            quit();
+            return false;
+        }
+        Action::Help => show_help(),
+        Action::Cancel => {
+            if !app.cancel() && app.mode == AppMode::Search {
+                app.switch_to_view();
+            }
+        }
+        Action::SwitchMode => {
+            if app.mode == AppMode::Search {
+                app.switch_to_view();
+            } else {
+                app.switch_to_search();
+            }
         }
-        (KeyModifiers::CONTROL, KeyCode::Char('h')) => {
-            show_help();
+
+        // 搜索模式
+        Action::Search => {
+            if let Err(e) = app.submit_search() {
+                err(e.to_string());
+            }
         }
-        (_, KeyCode::Char(char)) => {
-            let mode = app.input.handle_char(char);
+        Action::PrevHistory => {
+            let mode = app.input.prev_history(app.history.get());
             app.statusline.set_mode(mode);
-            redraw();
         }
-        (_, KeyCode::Enter) => match app.search(None) {
-            Ok(_) => redraw(),
-            Err(e) => {
+        Action::NextHistory => {
+            let mode = app.input.next_history(app.history.get());
+            app.statusline.set_mode(mode);
+        }
+        Action::ReverseSearch => {
+            app.input.reverse_search(app.history.get());
+        }
+        Action::Complete => app.complete(true),
+        Action::CompletePrev => app.complete(false),
+        Action::CursorLeft => app.input.left(),
+        Action::CursorRight => app.input.right(),
+        Action::LineStart => app.input.home(),
+        Action::LineEnd => app.input.end(),
+        Action::WordLeft => app.input.word_left(),
+        Action::WordRight => app.input.word_right(),
+        Action::Backspace => {
+            let mode = app.input.handle_backspace();
+            app.statusline.set_mode(mode);
+        }
+        Action::DeleteChar => {
+            let mode = app.input.delete();
+            app.statusline.set_mode(mode);
+        }
+        Action::DeleteWord => {
+            let mode = app.input.delete_word();
+            app.statusline.set_mode(mode);
+        }
+        Action::DeleteToStart => {
+            let mode = app.input.delete_to_start();
+            app.statusline.set_mode(mode);
+        }
+        Action::DeleteToEnd => {
+            let mode = app.input.delete_to_end();
+            app.statusline.set_mode(mode);
+        }
+
+        // 详情页和弹窗里用来滚动
+        Action::NextRow
+        | Action::PrevRow
+        | Action::NextRows
+        | Action::PrevRows
+        | Action::FirstRow
+        | Action::LastRow
+        | Action::HalfPageDown
+        | Action::HalfPageUp
+            if matches!(app.mode, AppMode::Detail | AppMode::Popup) =>
+        {
+            let state = match app.mode {
+                AppMode::Popup => &mut app.popup.scroll,
+                _ => app.project_detail.scroll_mut(),
+            };
+            scroll(state, action, count);
+        }
+
+        // 浏览模式
+        Action::NextRow => app.content.next(times),
+        Action::PrevRow => app.content.prev(times),
+        Action::NextRows => app.content.next(app.page_size.saturating_mul(times)),
+        Action::PrevRows => app.content.prev(app.page_size.saturating_mul(times)),
+        // 带数字时跳到第几行，和 vim 一样
+        Action::FirstRow | Action::LastRow if count.is_some() => app.content.goto(times),
+        Action::FirstRow => app.content.first(),
+        Action::LastRow => app.content.last(),
+        Action::ScreenTop => app.content.screen_top(times),
+        Action::ScreenMiddle => app.content.screen_middle(),
+        Action::ScreenBottom => app.content.screen_bottom(times),
+        Action::HalfPageDown => app
+            .content
+            .next(app.content.half_screen().saturating_mul(times)),
+        Action::HalfPageUp => app
+            .content
+            .prev(app.content.half_screen().saturating_mul(times)),
+        Action::JumpVolume => app.jump_volume(),
+        Action::NextPage => app.next_page(times),
+        Action::PrevPage => app.prev_page(times),
+        Action::OpenDetail => {
+            if let Err(e) = app.display_detail() {
                 err(e.to_string());
-                redraw();
             }
+        }
+        Action::StarHg => {
+            if let Err(e) = app.open_browser(Some("https://github.com/521xueweihan/HelloGitHub")) {
+                err(e.to_string());
+            }
+        }
+        Action::Export => match app.export() {
+            Ok(path) => tips(format!("已导出到 {}", path.display())),
+            Err(e) => err(e.to_string()),
         },
-        (_, KeyCode::Backspace) => {
-            app.input.handle_backspace();
-            redraw();
+        Action::ToggleSeen => {
+            if let Err(e) = app.toggle_seen() {
+                err(e.to_string());
+            }
+        }
+        Action::NextUnseen => {
+            if !app.next_unseen() {
+                tips("没有未读的项目了".into());
+            }
+        }
+        Action::MarkAllSeen => {
+            if let Err(e) = app.mark_all_seen() {
+                err(e.to_string());
+            }
         }
-        _ => {}
-    }
-}
 
-/// 浏览模式
-fn handle_view(key_modifier: KeyModifiers, key_code: KeyCode, app: &mut App) {
-    match (key_modifier, key_code) {
-        (_, KeyCode::Char('g')) => {
-            if GG_COMBINE.load(std::sync::atomic::Ordering::Relaxed) {
-                app.content.first();
-                redraw();
-                GG_COMBINE.store(false, std::sync::atomic::Ordering::Relaxed);
-            } else {
-                GG_COMBINE.store(true, std::sync::atomic::Ordering::Relaxed);
+        // 浏览模式和详情页
+        Action::ToggleLayout => {
+            // 布局的变化一眼就能看到，只提示保存失败
+            if let Err(e) = app.toggle_layout() {
+                err(format!("布局已切换，但没能保存：{:#}", e));
             }
         }
-        (_, KeyCode::Home) => {
-            app.content.first();
-            redraw();
+        Action::OpenBrowser => {
+            // 浏览器打开项目地址
+            if let Err(e) = app.open_browser(None) {
+                err(e.to_string());
+            }
         }
-        (key_modifier, key_code) => {
-            GG_COMBINE.store(false, std::sync::atomic::Ordering::Relaxed);
-            match (key_modifier, key_code) {
-                (KeyModifiers::CONTROL, KeyCode::Char('k'))
-                | (KeyModifiers::CONTROL, KeyCode::Up) => {
-                    // switch to view
-                    app.switch_to_search();
-                    redraw();
-                }
-                (KeyModifiers::CONTROL, KeyCode::Char('h')) => {
-                    show_help();
-                }
-                (_, KeyCode::Char('j')) | (_, KeyCode::Down) => {
-                    app.content.next(1);
-                    redraw();
-                }
-                (_, KeyCode::Char('k')) | (_, KeyCode::Up) => {
-                    app.content.prev(1);
-                    redraw();
-                }
-                (_, KeyCode::Char('d')) | (_, KeyCode::PageDown) => {
-                    app.content.next(5);
-                    redraw();
-                }
-                (_, KeyCode::Char('u')) | (_, KeyCode::PageUp) => {
-                    app.content.prev(5);
-                    redraw();
-                }
-                (_, KeyCode::Char('G')) | (_, KeyCode::End) => {
-                    app.content.last();
-                    redraw();
-                }
-                (_, KeyCode::Char('s')) => {
-                    app.open_browser(Some("https://github.com/521xueweihan/HelloGitHub"))
-                        .unwrap();
-                }
-                (_, KeyCode::Char('o')) | (KeyModifiers::CONTROL, KeyCode::Right) => {
-                    // 进入详情页
-                    app.display_detail().unwrap();
-                    redraw();
-                }
-                (_, KeyCode::Char('l')) | (_, KeyCode::Right) => {
-                    app.next_page().unwrap();
-                    redraw();
-                }
-                (_, KeyCode::Char('h')) | (_, KeyCode::Left) => {
-                    app.prev_page().unwrap();
-                    redraw();
-                }
-                (_, KeyCode::Enter) => {
-                    // 浏览器打开项目地址
-                    app.open_browser(None).unwrap();
-                }
-                _ => {}
+        Action::Bookmark => match app.toggle_bookmark() {
+            Ok(msg) => tips(msg),
+            Err(e) => err(e.to_string()),
+        },
+
+        // 详情页
+        Action::CloseDetail => app.mode = AppMode::View,
+        Action::EditNote => {
+            if let Err(e) = app.edit_note() {
+                err(e.to_string());
             }
         }
+        Action::ToggleReadme => app.toggle_readme(),
+    }
+    redraw();
+    true
+}
+
+/// 按移动的动作滚动，带数字的 gg、G 滚动到第几行
+fn scroll(state: &mut ScrollState, action: Action, count: Option<usize>) {
+    let times = count.unwrap_or(1);
+    let half = (state.page() / 2).max(1);
+    match action {
+        Action::NextRow => state.down(times),
+        Action::PrevRow => state.up(times),
+        Action::NextRows => state.down(state.page().saturating_mul(times)),
+        Action::PrevRows => state.up(state.page().saturating_mul(times)),
+        Action::HalfPageDown => state.down(half.saturating_mul(times)),
+        Action::HalfPageUp => state.up(half.saturating_mul(times)),
+        Action::FirstRow | Action::LastRow if count.is_some() => state.to(times - 1),
+        Action::FirstRow => state.reset(),
+        Action::LastRow => state.bottom(),
+        _ => {}
     }
 }
 
-fn handle_popup(_: KeyModifiers, _: KeyCode, app: &mut App) {
+/// 输入框中按 Ctrl-R 反向搜索历史记录
+fn handle_reverse_search(key_modifier: KeyModifiers, key_code: KeyCode, app: &mut App) {
+    let mode = match (key_modifier, key_code) {
+        (KeyModifiers::CONTROL, KeyCode::Char('r')) => app.input.reverse_search(app.history.get()),
+        (KeyModifiers::CONTROL, KeyCode::Char('g')) | (_, KeyCode::Esc) => {
+            app.input.finish_reverse(false)
+        }
+        (KeyModifiers::CONTROL, _) => return,
+        (_, KeyCode::Char(char)) => app.input.reverse_input(app.history.get(), Some(char)),
+        (_, KeyCode::Backspace) => app.input.reverse_input(app.history.get(), None),
+        (_, KeyCode::Enter) => {
+            app.input.finish_reverse(true);
+            app.statusline.set_mode(app.input.mode);
+            if let Err(e) = app.submit_search() {
+                err(e.to_string());
+            }
+            redraw();
+            return;
+        }
+        // 其他按键接受找到的记录，留在输入框继续编辑
+        _ => app.input.finish_reverse(true),
+    };
+    app.statusline.set_mode(mode);
+    redraw();
+}
+
+/// 按任意键或者点击鼠标关闭弹窗
+fn handle_popup(app: &mut App) {
+    app.keymap.reset();
     app.mode = AppMode::Search;
     redraw();
 }
 
-fn handle_detail(key_modifier: KeyModifiers, key_code: KeyCode, app: &mut App) {
+/// 编辑笔记
+fn handle_edit(key_modifier: KeyModifiers, key_code: KeyCode, app: &mut App) {
     match (key_modifier, key_code) {
-        (_, KeyCode::Char('o')) | (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Left) => {
-            app.mode = AppMode::View;
-            redraw();
-        }
-        (_, KeyCode::Enter) => {
-            // 浏览器打开项目地址
-            app.open_browser(None).unwrap();
+        (_, KeyCode::Esc) => app.mode = AppMode::Detail,
+        (KeyModifiers::CONTROL, KeyCode::Char('s')) => {
+            if let Err(e) = app.save_note() {
+                err(e.to_string());
+            }
         }
+        (_, KeyCode::Tab) | (_, KeyCode::BackTab) => app.switch_editor(),
+        (_, KeyCode::Enter) if app.editing_tags() => app.switch_editor(),
+        (_, KeyCode::Enter) => app.editor().newline(),
+        (_, KeyCode::Backspace) => app.editor().backspace(),
+        (_, KeyCode::Delete) => app.editor().delete(),
+        (_, KeyCode::Left) => app.editor().left(),
+        (_, KeyCode::Right) => app.editor().right(),
+        (_, KeyCode::Up) => app.editor().up(),
+        (_, KeyCode::Down) => app.editor().down(),
+        (_, KeyCode::Home) => app.editor().home(),
+        (_, KeyCode::End) => app.editor().end(),
+        (KeyModifiers::CONTROL, _) => {}
+        (_, KeyCode::Char(c)) => app.editor().insert(c),
         _ => {}
     }
+    redraw();
 }
 
 pub fn handle_notify(notify_app: Arc<Mutex<App>>) {
@@ -305,9 +561,10 @@ pub fn handle_notify(notify_app: Arc<Mutex<App>>) {
         show_help();
     }
 
+    // 同时驱动时钟和加载动画
     std::thread::spawn(move || loop {
         tick();
-        std::thread::sleep(Duration::from_secs(1));
+        std::thread::sleep(Duration::from_millis(100));
     });
 
     let notify_recv = NOTIFY.1.clone();
@@ -315,8 +572,43 @@ pub fn handle_notify(notify_app: Arc<Mutex<App>>) {
     loop {
         if let Ok(HGEvent::NotifyEvent(notify)) = notify_recv.recv() {
             match notify {
-                Notify::Redraw | Notify::Tick => {
+                Notify::Redraw => {
+                    let mut app = notify_app.lock().unwrap();
+
+                    draw::redraw(&mut app);
+                }
+                Notify::Tick => {
+                    let mut app = notify_app.lock().unwrap();
+
+                    if app.statusline.tick() {
+                        draw::redraw(&mut app);
+                    }
+                }
+                Notify::Fetched(id, job, result) => {
+                    let mut app = notify_app.lock().unwrap();
+                    if let Err(e) = app.finish(id, job, result) {
+                        err(e.to_string());
+                    }
+
+                    draw::redraw(&mut app);
+                }
+                Notify::Image(src, result) => {
                     let mut app = notify_app.lock().unwrap();
+                    app.project_detail.set_image(&src, result);
+
+                    draw::redraw(&mut app);
+                }
+                Notify::Graphics(src, area, data) => {
+                    let mut app = notify_app.lock().unwrap();
+                    if let Some(graphics) = &mut app.graphics {
+                        graphics.encoded(src, area, data);
+                    }
+
+                    draw::redraw(&mut app);
+                }
+                Notify::Readme(url, result) => {
+                    let mut app = notify_app.lock().unwrap();
+                    app.project_detail.set_readme(&url, result);
 
                     draw::redraw(&mut app);
                 }
@@ -327,9 +619,45 @@ pub fn handle_notify(notify_app: Arc<Mutex<App>>) {
                     draw::redraw(&mut app);
                 }
                 Notify::Quit => {
+                    terminal::restore();
                     break;
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{app, project};
+
+    fn press(app: &mut App, modifiers: KeyModifiers, keys: &str) {
+        for c in keys.chars() {
+            handle_key(KeyEvent::new(KeyCode::Char(c), modifiers), app);
+        }
+    }
+
+    #[test]
+    fn test_huge_count() {
+        let mut app = app("count");
+        app.content
+            .add_projects(vec![project("ncdu", "C"), project("black", "Python")]);
+        app.switch_to_view();
+
+        // 20 位的数字后面接翻页、半屏，不会溢出
+        for keys in ["d", "u"] {
+            press(&mut app, KeyModifiers::NONE, "99999999999999999999");
+            press(&mut app, KeyModifiers::NONE, keys);
+        }
+        press(&mut app, KeyModifiers::NONE, "99999999999999999999");
+        press(&mut app, KeyModifiers::CONTROL, "d");
+        assert_eq!(Some(1), app.content.tstate.selected());
+
+        app.project_detail.scroll_mut().update(100, 10);
+        app.mode = AppMode::Detail;
+        press(&mut app, KeyModifiers::NONE, "99999999999999999999");
+        press(&mut app, KeyModifiers::NONE, "d");
+        assert_eq!(90, app.project_detail.scroll_mut().offset());
+    }
+}
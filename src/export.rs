@@ -0,0 +1,251 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::parse::LastParse;
+use crate::widget::content::{Category, Project};
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+
+    Csv,
+
+    /// 仿照 HelloGitHub 月刊的格式，按类别分组
+    #[default]
+    Markdown,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Markdown => "md",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let format = match format.to_lowercase().as_ref() {
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            "markdown" | "md" => Format::Markdown,
+            _ => bail!("不支持的导出格式 '{}'，可选 json、csv、markdown", format),
+        };
+        Ok(format)
+    }
+}
+
+/// 把项目列表序列化成指定格式
+pub fn export(projects: &[Project], last_parse: &LastParse, format: Format) -> Result<String> {
+    let text = match format {
+        Format::Json => serde_json::to_string_pretty(projects)? + "\n",
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for project in projects {
+                writer.serialize(project)?;
+            }
+            String::from_utf8(writer.into_inner()?)?
+        }
+        Format::Markdown => markdown(projects, last_parse),
+    };
+    Ok(text)
+}
+
+/// 导出文件名（不含扩展名），如 `hellogithub-72`、`hellogithub-rust-2`
+pub fn file_stem(last_parse: &LastParse, page_no: usize) -> String {
+    match last_parse {
+        LastParse::Volume(volume) => format!("hellogithub-{}", volume),
+        LastParse::Category(category) => format!(
+            "hellogithub-{}-{}",
+            category
+                .replace(" 项目", "")
+                .to_lowercase()
+                .replace(' ', "-"),
+            page_no
+        ),
+        LastParse::Search => "hellogithub-search".into(),
+        LastParse::Favorite => format!("hellogithub-favorites-{}", page_no),
+        LastParse::Tag(tag) if tag.is_empty() => format!("hellogithub-tags-{}", page_no),
+        LastParse::Tag(tag) => format!(
+            "hellogithub-tag-{}-{}",
+            tag.to_lowercase().replace([' ', '/', '\\'], "-"),
+            page_no
+        ),
+    }
+}
+
+/// 导出文件的路径，同名文件已经存在时在名字后面加上序号，不覆盖之前导出的文件
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let path = dir.join(format!("{}.{}", stem, extension));
+    if !path.exists() {
+        return path;
+    }
+    (1..)
+        .map(|no| dir.join(format!("{}-{}.{}", stem, no, extension)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+fn title(last_parse: &LastParse) -> String {
+    match last_parse {
+        LastParse::Volume(volume) => format!("HelloGitHub 第 {} 期", volume),
+        LastParse::Category(category) => format!("HelloGitHub {}", category),
+        LastParse::Search => "HelloGitHub 搜索结果".into(),
+        LastParse::Favorite => "HelloGitHub 收藏夹".into(),
+        LastParse::Tag(tag) if tag.is_empty() => "HelloGitHub 打过标签的项目".into(),
+        LastParse::Tag(tag) => format!("HelloGitHub 标签：{}", tag),
+    }
+}
+
+/// 按类别分组，类别按第一次出现的顺序排列，序号在整篇里连续
+fn markdown(projects: &[Project], last_parse: &LastParse) -> String {
+    let mut groups: Vec<(&str, Vec<&Project>)> = Vec::new();
+    for project in projects {
+        match groups.iter_mut().find(|(c, _)| *c == project.category) {
+            Some((_, group)) => group.push(project),
+            None => groups.push((&project.category, vec![project])),
+        }
+    }
+
+    let mut text = format!("# {}\n", title(last_parse));
+    let mut no = 0;
+    for (category, group) in groups {
+        let heading = Category::try_from(category.to_string())
+            .map(Category::to_zh)
+            .unwrap_or_else(|_| category.to_string());
+        let _ = write!(text, "\n## {}\n", heading);
+
+        for project in group {
+            no += 1;
+            let _ = write!(
+                text,
+                "\n{}、[{}]({})：{}\n\n> 第 {} 期 | Star {} | Watch {} | Fork {}\n",
+                no,
+                project.name,
+                project.url,
+                project.desc,
+                project.volume,
+                project.star,
+                project.watch,
+                project.fork
+            );
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn projects() -> Vec<Project> {
+        vec![
+            Project::new(
+                "ncdu",
+                72,
+                "C 项目",
+                "https://github.com/rofl0r/ncdu",
+                "命令行磁盘空间分析工具。",
+                "1.1k",
+                "30",
+                "80",
+            ),
+            Project::new(
+                "black",
+                72,
+                "Python 项目",
+                "https://github.com/psf/black",
+                "不妥协的 Python 代码格式化工具, 支持在线试用。",
+                "31.2k",
+                "N/A",
+                "N/A",
+            ),
+            Project::new(
+                "tinyhttpd",
+                72,
+                "C 项目",
+                "https://github.com/EZLippi/Tinyhttpd",
+                "不到 500 行的超轻量 HTTP 服务器。",
+                "9.6k",
+                "N/A",
+                "N/A",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_markdown() {
+        let text = export(&projects(), &LastParse::Volume(72), Format::Markdown).unwrap();
+        let expect = "# HelloGitHub 第 72 期
+
+## C 项目
+
+1、[ncdu](https://github.com/rofl0r/ncdu)：命令行磁盘空间分析工具。
+
+> 第 72 期 | Star 1.1k | Watch 30 | Fork 80
+
+2、[tinyhttpd](https://github.com/EZLippi/Tinyhttpd)：不到 500 行的超轻量 HTTP 服务器。
+
+> 第 72 期 | Star 9.6k | Watch N/A | Fork N/A
+
+## Python 项目
+
+3、[black](https://github.com/psf/black)：不妥协的 Python 代码格式化工具, 支持在线试用。
+
+> 第 72 期 | Star 31.2k | Watch N/A | Fork N/A
+";
+        assert_eq!(expect, text);
+    }
+
+    #[test]
+    fn test_json_and_csv() {
+        let json = export(&projects(), &LastParse::Search, Format::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!("ncdu", value[0]["name"]);
+        assert_eq!(72, value[0]["volume"]);
+        assert_eq!("C", value[0]["category"]);
+
+        let csv = export(&projects(), &LastParse::Search, Format::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(4, lines.len());
+        assert_eq!(
+            "name,volume,category,url,desc,star,watch,fork,image",
+            lines[0]
+        );
+        // 介绍里有逗号时要加引号
+        assert!(lines[2].contains("\"不妥协的 Python 代码格式化工具, 支持在线试用。\""));
+    }
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!("hellogithub-72", file_stem(&LastParse::Volume(72), 1));
+        assert_eq!(
+            "hellogithub-rust-2",
+            file_stem(&LastParse::Category("Rust 项目".into()), 2)
+        );
+        assert_eq!(
+            "hellogithub-favorites-3",
+            file_stem(&LastParse::Favorite, 3)
+        );
+        assert_eq!(
+            "hellogithub-tags-1",
+            file_stem(&LastParse::Tag("".into()), 1)
+        );
+        assert_eq!(
+            "hellogithub-tag-cli-tools-1",
+            file_stem(&LastParse::Tag("CLI/Tools".into()), 1)
+        );
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
@@ -1,11 +1,19 @@
+#[cfg(test)]
+use std::cell::RefCell;
 use std::sync::Mutex;
+use std::time::Duration;
 
-use anyhow::{bail, Result};
+use anyhow::bail;
 use cached::proc_macro::cached;
+use once_cell::sync::OnceCell;
+use reqwest::blocking::Client;
 
 use crate::{
     app::SearchMode,
     app_global::HG_INFO,
+    cache::{PageCache, PageKind},
+    config::{CacheConfig, NetworkConfig},
+    error::{HgError, Result},
     parse::{parse_hg_info, Info},
     widget::content::Category,
 };
@@ -14,84 +22,510 @@ use lazy_static::lazy_static;
 
 lazy_static! {
     static ref LOCK: Mutex<()> = Mutex::new(());
+    static ref FETCHER: OnceCell<Box<dyn Fetcher>> = OnceCell::new();
+    static ref BASE_URL: OnceCell<String> = OnceCell::new();
+    static ref CACHE: OnceCell<Option<PageCache>> = OnceCell::new();
+    static ref OFFLINE: OnceCell<bool> = OnceCell::new();
 }
 
-/// HelloGitHub 路径前缀
-const BASE_PATH: &str = "https://hellogithub.com/periodical";
+#[cfg(test)]
+thread_local! {
+    /// 测试里只对当前线程生效的请求方式，不受全局设置和其他测试的影响
+    static THREAD_FETCHER: RefCell<Option<Box<dyn Fetcher>>> = RefCell::new(None);
+}
+
+/// HelloGitHub 默认地址
+pub const DEFAULT_BASE_URL: &str = "https://hellogithub.com";
+
+/// GitHub 上文件原始内容的地址
+const RAW_BASE_URL: &str = "https://raw.githubusercontent.com";
+
+/// 按顺序尝试的 README 文件名
+const README_NAMES: &[&str] = &["README.md", "readme.md", "README.markdown", "README"];
+
+/// 发起 HTTP 请求，可以替换成本地镜像或者测试用的桩
+pub trait Fetcher: Sync + Send {
+    fn get(&self, url: &str) -> Result<String>;
+
+    /// 下载图片这类二进制内容
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        self.get(url).map(String::into_bytes)
+    }
+}
+
+/// 基于 reqwest 的默认实现
+pub struct HttpFetcher {
+    client: Client,
+}
+
+impl HttpFetcher {
+    pub fn new(network: &NetworkConfig) -> Result<HttpFetcher> {
+        let mut builder = Client::builder().timeout(Duration::from_secs(network.timeout));
+        if let Some(proxy) = &network.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some(user_agent) = &network.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(HttpFetcher {
+            client: builder.build()?,
+        })
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn get(&self, url: &str) -> Result<String> {
+        let resp = self.client.get(url).send()?;
+        if !resp.status().is_success() {
+            return Err(HgError::Status {
+                url: url.to_string(),
+                status: resp.status().as_u16(),
+            });
+        }
+        Ok(resp.text()?)
+    }
+
+    fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let resp = self.client.get(url).send()?;
+        if !resp.status().is_success() {
+            return Err(HgError::Status {
+                url: url.to_string(),
+                status: resp.status().as_u16(),
+            });
+        }
+        Ok(resp.bytes()?.to_vec())
+    }
+}
+
+/// 根据网络和缓存设置初始化请求方式、站点地址和本地缓存，需要在第一次请求前调用
+pub fn init(network: &NetworkConfig, cache: &CacheConfig) -> anyhow::Result<()> {
+    set_fetcher(Box::new(HttpFetcher::new(network)?));
+    // 重复初始化时保留第一次的设置
+    let _ = BASE_URL.set(network.base_url.trim_end_matches('/').to_string());
+    let page_cache = match &cache.dir {
+        _ if !cache.enabled => None,
+        Some(dir) => Some(PageCache::new(dir)),
+        None => PageCache::default_dir().map(PageCache::new),
+    };
+    let _ = CACHE.set(page_cache);
+    let _ = OFFLINE.set(cache.offline);
+    Ok(())
+}
+
+/// 替换默认的请求方式，只有第一次调用生效
+pub fn set_fetcher(fetcher: Box<dyn Fetcher>) {
+    let _ = FETCHER.set(fetcher);
+}
 
-pub fn fetch(text: impl Into<String>, mode: SearchMode) -> Result<String> {
+fn fetcher() -> &'static dyn Fetcher {
+    FETCHER
+        .get_or_init(|| {
+            Box::new(HttpFetcher::new(&NetworkConfig::default()).expect("无法创建 HTTP client"))
+        })
+        .as_ref()
+}
+
+/// 当前线程替换过请求方式时用它请求，不读写本地缓存
+#[cfg(test)]
+fn thread_fetch<R>(f: impl FnOnce(&dyn Fetcher) -> R) -> Option<R> {
+    THREAD_FETCHER.with(|fetcher| fetcher.borrow().as_deref().map(f))
+}
+
+#[cfg(not(test))]
+fn thread_fetch<R>(_: impl FnOnce(&dyn Fetcher) -> R) -> Option<R> {
+    None
+}
+
+/// 优先读取本地缓存，离线模式下只读缓存，请求失败时退回到过期的缓存
+fn fetch_page(kind: PageKind, url: &str) -> Result<String> {
+    if let Some(result) = thread_fetch(|fetcher| fetcher.get(url)) {
+        return result;
+    }
+    let cache = CACHE.get().and_then(|cache| cache.as_ref());
+    let offline = *OFFLINE.get().unwrap_or(&false);
+
+    let cache = match cache {
+        Some(cache) => cache,
+        None => return fetcher().get(url),
+    };
+
+    if offline {
+        return cache
+            .get_stale(url)
+            .ok_or_else(|| HgError::Offline(url.to_string()));
+    }
+
+    if let Some(html) = cache.get(url, kind.ttl()) {
+        return Ok(html);
+    }
+
+    match fetcher().get(url) {
+        Ok(html) => {
+            // 缓存写失败不影响正常浏览
+            let _ = cache.put(url, &html);
+            Ok(html)
+        }
+        Err(e) => cache.get_stale(url).ok_or(e),
+    }
+}
+
+/// 下载图片，图片不会变，有缓存时直接用缓存
+pub fn fetch_image(src: &str) -> Result<Vec<u8>> {
+    let url = image_url(base_url(), src);
+    if let Some(result) = thread_fetch(|fetcher| fetcher.get_bytes(&url)) {
+        return result;
+    }
+    let cache = CACHE.get().and_then(|cache| cache.as_ref());
+
+    if let Some(bytes) = cache.and_then(|cache| cache.get_image(&url)) {
+        return Ok(bytes);
+    }
+    if *OFFLINE.get().unwrap_or(&false) {
+        return Err(HgError::Offline(url));
+    }
+
+    let bytes = fetcher().get_bytes(&url)?;
+    if let Some(cache) = cache {
+        let _ = cache.put_image(&url, &bytes);
+    }
+    Ok(bytes)
+}
+
+pub fn base_url() -> &'static str {
+    BASE_URL.get_or_init(|| DEFAULT_BASE_URL.to_string())
+}
+
+pub fn volume_url(base_url: &str, volume: usize) -> String {
+    format!("{}/periodical/volume/{:0>2}/", base_url, volume)
+}
+
+pub fn category_url(base_url: &str, category: Category, page_no: usize) -> String {
+    format!(
+        "{}/periodical/category/{}/?page={}",
+        base_url,
+        category.to_zh(),
+        page_no
+    )
+}
+
+/// 页面里的图片可能是相对地址，补全成完整的地址
+pub fn image_url(base_url: &str, src: &str) -> String {
+    if src.starts_with("//") {
+        format!("https:{}", src)
+    } else if src.starts_with('/') {
+        format!("{}{}", base_url, src)
+    } else {
+        src.to_string()
+    }
+}
+
+/// GitHub 项目 README 可能的原始地址，其他网站的项目返回 None
+pub fn readme_urls(url: &str) -> Option<Vec<String>> {
+    let path = url
+        .trim()
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.trim().strip_prefix("http://github.com/"))?;
+    let mut parts = path.split('/').filter(|part| !part.is_empty());
+    let owner = parts.next()?;
+    let repo = parts.next()?.trim_end_matches(".git");
+    Some(
+        README_NAMES
+            .iter()
+            .map(|name| format!("{}/{}/{}/HEAD/{}", RAW_BASE_URL, owner, repo, name))
+            .collect(),
+    )
+}
+
+pub fn search_url(base_url: &str, wait_search: &str) -> String {
+    format!("{}/periodical/search?q={}", base_url, wait_search)
+}
+
+pub fn fetch(text: impl Into<String>, mode: SearchMode) -> anyhow::Result<String> {
     let html = match mode {
-        SearchMode::Normal => search(text.into()),
+        SearchMode::Normal => search(text.into())?,
         SearchMode::Volume => match &text.into()[1..].parse::<usize>() {
-            Ok(volume) if volume >= &1 => fetch_volume(*volume),
+            Ok(volume) if volume >= &1 => fetch_volume(*volume)?,
             _ => bail!("请输入有效的期数大于 0 的数字！"),
         },
         SearchMode::Category => {
-            fetch_category(Category::try_from(text.into()[1..].to_string()).unwrap(), 1)
+            fetch_category(Category::try_from(text.into()[1..].to_string())?, 1)?
         }
+        SearchMode::Favorite | SearchMode::Tag => bail!("收藏夹、标签保存在本地，不需要请求"),
     };
 
     Ok(html)
 }
 
-#[cached]
-pub fn fetch_hg_info() -> Info {
-    let resp = reqwest::blocking::get("https://hellogithub.com").unwrap();
-    parse_hg_info(resp.text().unwrap())
+/// 首页信息，第一次调用时请求，失败时下次调用会重试
+pub fn hg_info() -> Result<&'static Info> {
+    HG_INFO.get_or_try_init(fetch_hg_info)
 }
 
-#[cached]
-pub fn fetch_volume(mut volume: usize) -> String {
-    if volume > HG_INFO.max_volume {
-        volume = HG_INFO.max_volume;
+#[cached(result = true)]
+pub fn fetch_hg_info() -> Result<Info> {
+    parse_hg_info(fetch_page(PageKind::Home, base_url())?)
+}
+
+#[cached(result = true)]
+pub fn fetch_volume(mut volume: usize) -> Result<String> {
+    let max_volume = hg_info()?.max_volume;
+    if volume > max_volume {
+        volume = max_volume;
     }
     let _lock = LOCK.lock().unwrap();
-    let resp = reqwest::blocking::get(format!("{}/volume/{:0>2}/", BASE_PATH, volume)).unwrap();
-
-    resp.text().unwrap()
+    let kind = PageKind::Volume {
+        latest: volume == max_volume,
+    };
+    fetch_page(kind, &volume_url(base_url(), volume))
 }
 
-#[cached]
-pub fn fetch_category(category: Category, page_no: usize) -> String {
-    let url = format!(
-        "{}/category/{}/?page={}",
-        BASE_PATH,
-        category.to_zh(),
-        page_no
-    );
+#[cached(result = true)]
+pub fn fetch_category(category: Category, page_no: usize) -> Result<String> {
     let _lock = LOCK.lock().unwrap();
-    let resp = reqwest::blocking::get(url).unwrap();
-
-    resp.text().unwrap()
+    fetch_page(
+        PageKind::Category,
+        &category_url(base_url(), category, page_no),
+    )
 }
 
-#[cached]
-pub fn search(wait_search: String) -> String {
+#[cached(result = true)]
+pub fn search(wait_search: String) -> Result<String> {
     let _lock = LOCK.lock().unwrap();
-    let resp = reqwest::blocking::get(format!("{}/search?q={}", BASE_PATH, wait_search)).unwrap();
+    fetch_page(PageKind::Search, &search_url(base_url(), &wait_search))
+}
+
+/// 项目的 README 原文，`url` 是项目地址
+#[cached(result = true)]
+pub fn fetch_readme(url: String) -> Result<String> {
+    find_readme(&url, |raw_url| fetch_page(PageKind::Readme, raw_url))
+}
 
-    resp.text().unwrap()
+/// 依次尝试几个常见的文件名，404 或者离线时没有缓存就换下一个
+fn find_readme(url: &str, get: impl Fn(&str) -> Result<String>) -> Result<String> {
+    let raw_urls = readme_urls(url).ok_or_else(|| HgError::Unsupported(url.to_string()))?;
+    let last = raw_urls.len() - 1;
+    for (i, raw_url) in raw_urls.iter().enumerate() {
+        match get(raw_url) {
+            Err(HgError::Status { status: 404, .. }) => continue,
+            // 缓存的可能是后面的文件名，都没有缓存时报最后一个
+            Err(HgError::Offline(_)) if i < last => continue,
+            result => return result,
+        }
+    }
+    Err(HgError::NoReadme(url.to_string()))
 }
 
+#[cfg(test)]
 mod test {
     #[allow(unused_imports)]
     use super::*;
 
+    struct StubFetcher;
+
+    /// 按地址返回 `tests/fixtures` 里的页面，其他地址返回 404
+    struct FixtureFetcher;
+
+    impl Fetcher for FixtureFetcher {
+        fn get(&self, url: &str) -> Result<String> {
+            let path = url.strip_prefix(base_url()).unwrap_or(url);
+            let html = if path.is_empty() || path == "/" {
+                include_str!("../tests/fixtures/home.html")
+            } else if path.starts_with("/periodical/volume/") {
+                include_str!("../tests/fixtures/volume.html")
+            } else if path.starts_with("/periodical/category/") {
+                include_str!("../tests/fixtures/category.html")
+            } else if path.starts_with("/periodical/search") {
+                include_str!("../tests/fixtures/search.html")
+            } else {
+                return Err(HgError::Status {
+                    url: url.to_string(),
+                    status: 404,
+                });
+            };
+            Ok(html.to_string())
+        }
+    }
+
+    /// 把当前线程的请求换成本地页面
+    fn use_fixtures() {
+        THREAD_FETCHER.with(|fetcher| *fetcher.borrow_mut() = Some(Box::new(FixtureFetcher)));
+    }
+
+    impl Fetcher for StubFetcher {
+        fn get(&self, url: &str) -> Result<String> {
+            Ok(format!("<h1>{}</h1>", url))
+        }
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            "http://127.0.0.1:8000/periodical/volume/07/",
+            volume_url("http://127.0.0.1:8000", 7)
+        );
+        assert_eq!(
+            "http://127.0.0.1:8000/periodical/volume/72/",
+            volume_url("http://127.0.0.1:8000", 72)
+        );
+        assert_eq!(
+            "http://127.0.0.1:8000/periodical/category/Rust 项目/?page=2",
+            category_url("http://127.0.0.1:8000", Category::Rust, 2)
+        );
+        assert_eq!(
+            "http://127.0.0.1:8000/periodical/search?q=python",
+            search_url("http://127.0.0.1:8000", "python")
+        );
+        assert_eq!(
+            "http://127.0.0.1:8000/static/img/black.png",
+            image_url("http://127.0.0.1:8000", "/static/img/black.png")
+        );
+        assert_eq!(
+            "https://img.hellogithub.com/i/ncdu.png",
+            image_url("http://127.0.0.1:8000", "//img.hellogithub.com/i/ncdu.png")
+        );
+        assert_eq!(
+            "https://img.hellogithub.com/i/ncdu.png",
+            image_url(
+                "http://127.0.0.1:8000",
+                "https://img.hellogithub.com/i/ncdu.png"
+            )
+        );
+    }
+
+    #[test]
+    fn test_fetch_fixtures() {
+        use crate::parse::{LastParse, PARSER};
+
+        use_fixtures();
+        let parse = |html: Result<String>, mode: SearchMode| {
+            let (projects, last_parse) = PARSER[&mode].parse(html.unwrap()).unwrap();
+            let names: Vec<String> = projects.into_iter().map(|p| p.name).collect();
+            (names, last_parse)
+        };
+
+        assert_eq!(
+            (
+                vec!["black".into(), "Python-100-Days".into(), "rich".into()],
+                LastParse::Search
+            ),
+            parse(search("python".to_string()), SearchMode::Normal)
+        );
+        // 超过最新一期时取首页上的最新一期
+        assert_eq!(
+            (
+                vec!["ncdu".into(), "tinyhttpd".into(), "black".into()],
+                LastParse::Volume(72)
+            ),
+            parse(fetch_volume(100), SearchMode::Volume)
+        );
+        assert_eq!(
+            (
+                vec!["ripgrep".into(), "exa".into()],
+                LastParse::Category("Rust 项目".into())
+            ),
+            parse(fetch_category(Category::Rust, 1), SearchMode::Category)
+        );
+    }
+
+    #[test]
+    fn test_readme_urls() {
+        assert_eq!(
+            Some("https://raw.githubusercontent.com/psf/black/HEAD/README.md"),
+            readme_urls("https://github.com/psf/black")
+                .unwrap()
+                .first()
+                .map(String::as_str)
+        );
+        assert_eq!(
+            Some("https://raw.githubusercontent.com/psf/black/HEAD/readme.md"),
+            readme_urls("http://github.com/psf/black.git/tree/main/")
+                .unwrap()
+                .get(1)
+                .map(String::as_str)
+        );
+        assert!(readme_urls("https://github.com/psf").is_none());
+        assert!(readme_urls("https://gitee.com/psf/black").is_none());
+    }
+
+    #[test]
+    fn test_find_readme() {
+        // 只有小写文件名的 readme.md
+        let stub = |url: &str| {
+            if url.ends_with("/readme.md") {
+                StubFetcher.get(url)
+            } else {
+                Err(HgError::Status {
+                    url: url.to_string(),
+                    status: 404,
+                })
+            }
+        };
+        assert_eq!(
+            "<h1>https://raw.githubusercontent.com/rofl0r/ncdu/HEAD/readme.md</h1>",
+            find_readme("https://github.com/rofl0r/ncdu", stub).unwrap()
+        );
+
+        let not_found = |url: &str| -> Result<String> {
+            Err(HgError::Status {
+                url: url.to_string(),
+                status: 404,
+            })
+        };
+        assert!(matches!(
+            find_readme("https://github.com/rofl0r/ncdu", not_found),
+            Err(HgError::NoReadme(_))
+        ));
+
+        // 离线时只缓存了第二个文件名
+        let cached = |url: &str| {
+            if url.ends_with("/readme.md") {
+                StubFetcher.get(url)
+            } else {
+                Err(HgError::Offline(url.to_string()))
+            }
+        };
+        assert_eq!(
+            "<h1>https://raw.githubusercontent.com/rofl0r/ncdu/HEAD/readme.md</h1>",
+            find_readme("https://github.com/rofl0r/ncdu", cached).unwrap()
+        );
+        let offline = |url: &str| -> Result<String> { Err(HgError::Offline(url.to_string())) };
+        assert!(matches!(
+            find_readme("https://github.com/rofl0r/ncdu", offline),
+            Err(HgError::Offline(url)) if url.ends_with("/README")
+        ));
+
+        // 其他错误直接返回，不再尝试
+        let failed = |url: &str| -> Result<String> {
+            Err(HgError::Status {
+                url: url.to_string(),
+                status: 500,
+            })
+        };
+        assert!(matches!(
+            find_readme("https://github.com/rofl0r/ncdu", failed),
+            Err(HgError::Status { url, status: 500 }) if url.ends_with("/README.md")
+        ));
+        assert!(matches!(
+            find_readme("https://gitee.com/rofl0r/ncdu", stub),
+            Err(HgError::Unsupported(_))
+        ));
+    }
+
     #[test]
     #[ignore]
     fn test_volume() {
-        fetch_volume(72);
+        fetch_volume(72).unwrap();
     }
 
     #[test]
     #[ignore]
     fn test_category() {
-        fetch_category(Category::C, 1);
+        fetch_category(Category::C, 1).unwrap();
     }
 
     #[test]
     #[ignore]
     fn test_search() {
-        search("python".to_string());
+        search("python".to_string()).unwrap();
     }
 }
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// 最多保存的搜索记录条数
+const MAX_HISTORY: usize = 500;
+
+/// 搜索记录，最近的排在最后，重复的只保留最近一次
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    queries: Vec<String>,
+}
+
+impl History {
+    pub fn push(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.queries.retain(|q| q != query);
+        self.queries.push(query.to_string());
+        if self.queries.len() > MAX_HISTORY {
+            self.queries.drain(..self.queries.len() - MAX_HISTORY);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.queries.get(index).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    /// 在 `before` 之前（不含）往前找包含 `pattern` 的记录，和 shell 的 Ctrl-R 一样
+    pub fn rfind(&self, pattern: &str, before: usize) -> Option<usize> {
+        self.queries[..before.min(self.queries.len())]
+            .iter()
+            .rposition(|q| q.contains(pattern))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut history = History::default();
+        history.push("#72");
+        history.push("$rust");
+        history.push(" #72 ");
+        history.push("");
+        assert_eq!(2, history.len());
+        assert_eq!(Some("$rust"), history.get(0));
+        assert_eq!(Some("#72"), history.get(1));
+
+        for i in 0..MAX_HISTORY {
+            history.push(&i.to_string());
+        }
+        assert_eq!(MAX_HISTORY, history.len());
+        assert_eq!(Some("0"), history.get(0));
+    }
+
+    #[test]
+    fn test_rfind() {
+        let mut history = History::default();
+        for query in ["python", "$py", "rust", "python web"] {
+            history.push(query);
+        }
+        assert_eq!(Some(3), history.rfind("py", history.len()));
+        assert_eq!(Some(1), history.rfind("py", 3));
+        assert_eq!(Some(0), history.rfind("py", 1));
+        assert_eq!(None, history.rfind("py", 0));
+        assert_eq!(None, history.rfind("go", 4));
+        assert_eq!(Some(3), history.rfind("", 4));
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::config::IndexConfig;
+use crate::widget::content::Project;
+
+/// 本地搜索最多返回的项目数
+const MAX_RESULTS: usize = 50;
+
+/// 名称、类别、介绍的权重
+const NAME_WEIGHT: f32 = 3.0;
+const CATEGORY_WEIGHT: f32 = 2.0;
+const DESC_WEIGHT: f32 = 1.0;
+
+/// 索引文件的格式有变化时加一，旧文件需要重新同步
+const INDEX_VERSION: u32 = 1;
+
+/// 本地索引，没有执行过 `hgtui sync` 或者关闭了索引时为 `None`
+static INDEX: OnceCell<Option<Index>> = OnceCell::new();
+
+/// 读取本地索引，只有第一次调用生效
+pub fn init(config: &IndexConfig, data_dir: &Path) -> Result<()> {
+    let path = index_path(config, data_dir);
+    if !config.enabled || !path.exists() {
+        let _ = INDEX.set(None);
+        return Ok(());
+    }
+    let file = IndexFile::load(&path)?;
+    let index = (file.version == INDEX_VERSION).then(|| Index::new(file.projects));
+    let _ = INDEX.set(index);
+    Ok(())
+}
+
+/// 已经加载的本地索引
+pub fn get() -> Option<&'static Index> {
+    INDEX.get().and_then(|index| index.as_ref())
+}
+
+/// 索引文件路径，默认为数据目录下的 `index.json`
+pub fn index_path(config: &IndexConfig, data_dir: &Path) -> PathBuf {
+    config
+        .path
+        .clone()
+        .unwrap_or_else(|| data_dir.join("index.json"))
+}
+
+/// 保存在磁盘上的索引，只存项目，倒排表在加载时重新生成
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IndexFile {
+    pub version: u32,
+
+    pub projects: Vec<Project>,
+}
+
+impl IndexFile {
+    pub fn load(path: &Path) -> Result<IndexFile> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("无法读取索引文件 {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("索引文件 {} 已损坏，请重新执行 hgtui sync", path.display()))
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.version = INDEX_VERSION;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("无法写入索引文件 {}", path.display()))
+    }
+
+    /// 已经同步过的期数
+    pub fn volumes(&self) -> BTreeSet<usize> {
+        self.projects.iter().map(|project| project.volume).collect()
+    }
+
+    /// 用新抓取的内容替换某一期的项目
+    pub fn replace_volume(&mut self, volume: usize, projects: Vec<Project>) {
+        self.projects.retain(|project| project.volume != volume);
+        self.projects.extend(projects);
+    }
+}
+
+/// 倒排索引，词 => (项目下标, 加权词频)
+#[derive(Debug, Default)]
+pub struct Index {
+    projects: Vec<Project>,
+    terms: HashMap<String, Vec<(usize, f32)>>,
+}
+
+impl Index {
+    pub fn new(projects: Vec<Project>) -> Index {
+        let mut terms: HashMap<String, Vec<(usize, f32)>> = HashMap::new();
+        for (doc, project) in projects.iter().enumerate() {
+            let fields = [
+                (&project.name, NAME_WEIGHT),
+                (&project.category, CATEGORY_WEIGHT),
+                (&project.desc, DESC_WEIGHT),
+            ];
+            for (text, weight) in fields {
+                for token in tokenize(text) {
+                    let postings = terms.entry(token).or_default();
+                    match postings.last_mut() {
+                        Some((last, score)) if *last == doc => *score += weight,
+                        _ => postings.push((doc, weight)),
+                    }
+                }
+            }
+        }
+        Index { projects, terms }
+    }
+
+    /// 模糊搜索，英文词允许前缀匹配和少量拼写错误，中文按二元组匹配
+    ///
+    /// 英文词需要全部命中，中文二元组至少命中一半，按得分从高到低排列
+    pub fn search(&self, query: &str) -> Vec<Project> {
+        let mut tokens = tokenize(query);
+        tokens.sort();
+        tokens.dedup();
+        if tokens.is_empty() {
+            return vec![];
+        }
+
+        let ascii_count = tokens.iter().filter(|t| is_ascii_token(t)).count();
+        let cjk_required = (tokens.len() - ascii_count).div_ceil(2);
+        let n = self.projects.len() as f32;
+
+        // 项目下标 => (得分, 命中的英文词数, 命中的中文词数)
+        let mut hits: HashMap<usize, (f32, usize, usize)> = HashMap::new();
+        for token in &tokens {
+            let mut best: HashMap<usize, f32> = HashMap::new();
+            for (term, quality) in self.matching_terms(token) {
+                let postings = &self.terms[term];
+                let idf = (1.0 + n / postings.len() as f32).ln();
+                for (doc, tf) in postings {
+                    let score = quality * idf * tf;
+                    let entry = best.entry(*doc).or_default();
+                    *entry = entry.max(score);
+                }
+            }
+            for (doc, score) in best {
+                let hit = hits.entry(doc).or_default();
+                hit.0 += score;
+                if is_ascii_token(token) {
+                    hit.1 += 1;
+                } else {
+                    hit.2 += 1;
+                }
+            }
+        }
+
+        let query = query.trim().to_lowercase();
+        let mut result: Vec<(f32, &Project)> = hits
+            .into_iter()
+            .filter(|(_, (_, ascii, cjk))| *ascii == ascii_count && *cjk >= cjk_required)
+            .map(|(doc, (score, ..))| {
+                let project = &self.projects[doc];
+                let name = project.name.to_lowercase();
+                let bonus = if name == query {
+                    10.0
+                } else if name.contains(&query) {
+                    3.0
+                } else {
+                    0.0
+                };
+                (score + bonus, project)
+            })
+            .collect();
+
+        result.sort_by(|(a, pa), (b, pb)| {
+            b.total_cmp(a)
+                .then(pb.volume.cmp(&pa.volume))
+                .then(pa.name.cmp(&pb.name))
+        });
+        result
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, project)| project.clone())
+            .collect()
+    }
+
+    /// 与查询词匹配的索引词以及匹配程度
+    fn matching_terms<'a>(&'a self, token: &'a str) -> Vec<(&'a String, f32)> {
+        if !is_ascii_token(token) {
+            return self
+                .terms
+                .get_key_value(token)
+                .map(|(term, _)| vec![(term, 1.0)])
+                .unwrap_or_default();
+        }
+
+        let len = token.chars().count();
+        let max_distance = match len {
+            0..=3 => 0,
+            4..=7 => 1,
+            _ => 2,
+        };
+        self.terms
+            .keys()
+            .filter(|term| is_ascii_token(term))
+            .filter_map(|term| {
+                if term == token {
+                    Some((term, 1.0))
+                } else if len >= 2 && term.starts_with(token) {
+                    Some((term, 0.8))
+                } else if max_distance > 0 && edit_distance(term, token) <= max_distance {
+                    Some((term, 0.5))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn is_ascii_token(token: &str) -> bool {
+    token.is_ascii()
+}
+
+/// 分词：英文、数字按单词切分并转小写，中文切成相邻两个字的二元组，单个汉字保留原样
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk: Vec<char> = Vec::new();
+
+    fn flush_cjk(cjk: &mut Vec<char>, tokens: &mut Vec<String>) {
+        match cjk.len() {
+            0 => {}
+            1 => tokens.push(cjk[0].to_string()),
+            _ => tokens.extend(cjk.windows(2).map(|pair| pair.iter().collect())),
+        }
+        cjk.clear();
+    }
+
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            flush_cjk(&mut cjk, &mut tokens);
+            word.push(c.to_ascii_lowercase());
+        } else if c.is_alphanumeric() {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            cjk.push(c);
+        } else {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            flush_cjk(&mut cjk, &mut tokens);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    flush_cjk(&mut cjk, &mut tokens);
+    tokens
+}
+
+/// 编辑距离，相邻两个字符交换算一次编辑（如 pyhton => python）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn project(name: &str, volume: usize, category: &str, desc: &str) -> Project {
+        Project::new(name, volume, category, "", desc, "", "", "")
+    }
+
+    fn index() -> Index {
+        Index::new(vec![
+            project("ncdu", 72, "C", "命令行磁盘空间分析工具。"),
+            project("black", 72, "Python", "不妥协的 Python 代码格式化工具。"),
+            project("ripgrep", 19, "Rust", "比 grep 更快的命令行搜索工具。"),
+            project("exa", 72, "Rust", "用 Rust 写的 ls 替代品。"),
+            project("httpie", 10, "Python", "命令行 HTTP 客户端。"),
+        ])
+    }
+
+    fn names(projects: Vec<Project>) -> Vec<String> {
+        projects.into_iter().map(|project| project.name).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            vec!["用", "rust", "写的", "ls", "替代", "代品"],
+            tokenize("用 Rust 写的 ls 替代品。")
+        );
+        assert_eq!(vec!["命令", "令行", "c"], tokenize("命令行C"));
+        assert!(tokenize("，。!").is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let index = index();
+        // 名称完全匹配排在最前面
+        assert_eq!(vec!["ripgrep"], names(index.search("ripgrep")));
+        assert_eq!(vec!["exa", "ripgrep"], names(index.search("rust")));
+        // 前缀
+        assert_eq!(vec!["ripgrep"], names(index.search("ripg")));
+        // 拼写错误
+        assert_eq!(vec!["black"], names(index.search("pyhton black")));
+        // 中文
+        assert_eq!(
+            vec!["ncdu", "ripgrep", "httpie"],
+            names(index.search("命令行"))
+        );
+        assert_eq!(vec!["ncdu"], names(index.search("磁盘分析")));
+        assert!(index.search("golang").is_empty());
+        assert!(index.search("").is_empty());
+    }
+
+    #[test]
+    fn test_index_file() {
+        let path = std::env::temp_dir()
+            .join(format!("hgtui-index-{}", std::process::id()))
+            .join("index.json");
+        let mut file = IndexFile::default();
+        file.replace_volume(72, vec![project("ncdu", 72, "C", "")]);
+        file.replace_volume(19, vec![project("ripgrep", 19, "Rust", "")]);
+        file.replace_volume(72, vec![project("exa", 72, "Rust", "")]);
+        file.save(&path).unwrap();
+
+        let file = IndexFile::load(&path).unwrap();
+        assert_eq!(INDEX_VERSION, file.version);
+        assert_eq!(BTreeSet::from([19, 72]), file.volumes());
+        assert_eq!(vec!["ripgrep", "exa"], names(file.projects));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+
+/// 一次按键，修饰键 + 键码
+pub type Key = (KeyModifiers, KeyCode);
+
+/// 按键前输入的数字的上限，再大也没有意义，还会让后面的乘法溢出
+const MAX_COUNT: usize = 9999;
+
+/// 按键生效的模式，不同模式下同一个按键可以对应不同的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Search,
+    View,
+    Detail,
+    /// 内容太长需要滚动的弹窗，没有绑定的按键关闭弹窗
+    Popup,
+}
+
+/// 可以绑定按键的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Search,
+    Cancel,
+    SwitchMode,
+    Help,
+    Quit,
+    PrevHistory,
+    NextHistory,
+    ReverseSearch,
+    Complete,
+    CompletePrev,
+    CursorLeft,
+    CursorRight,
+    LineStart,
+    LineEnd,
+    WordLeft,
+    WordRight,
+    Backspace,
+    DeleteChar,
+    DeleteWord,
+    DeleteToStart,
+    DeleteToEnd,
+    NextRow,
+    PrevRow,
+    NextRows,
+    PrevRows,
+    FirstRow,
+    LastRow,
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    HalfPageDown,
+    HalfPageUp,
+    JumpVolume,
+    NextPage,
+    PrevPage,
+    OpenDetail,
+    CloseDetail,
+    OpenBrowser,
+    StarHg,
+    Export,
+    Bookmark,
+    ToggleSeen,
+    NextUnseen,
+    MarkAllSeen,
+    EditNote,
+    ToggleReadme,
+    ToggleLayout,
+}
+
+/// 配置文件中的动作名称
+const ACTIONS: &[(&str, Action)] = &[
+    ("search", Action::Search),
+    ("cancel", Action::Cancel),
+    ("switch_mode", Action::SwitchMode),
+    ("help", Action::Help),
+    ("quit", Action::Quit),
+    ("prev_history", Action::PrevHistory),
+    ("next_history", Action::NextHistory),
+    ("reverse_search", Action::ReverseSearch),
+    ("complete", Action::Complete),
+    ("complete_prev", Action::CompletePrev),
+    ("cursor_left", Action::CursorLeft),
+    ("cursor_right", Action::CursorRight),
+    ("line_start", Action::LineStart),
+    ("line_end", Action::LineEnd),
+    ("word_left", Action::WordLeft),
+    ("word_right", Action::WordRight),
+    ("backspace", Action::Backspace),
+    ("delete_char", Action::DeleteChar),
+    ("delete_word", Action::DeleteWord),
+    ("delete_to_start", Action::DeleteToStart),
+    ("delete_to_end", Action::DeleteToEnd),
+    ("next_row", Action::NextRow),
+    ("prev_row", Action::PrevRow),
+    ("next_rows", Action::NextRows),
+    ("prev_rows", Action::PrevRows),
+    ("first_row", Action::FirstRow),
+    ("last_row", Action::LastRow),
+    ("screen_top", Action::ScreenTop),
+    ("screen_middle", Action::ScreenMiddle),
+    ("screen_bottom", Action::ScreenBottom),
+    ("half_page_down", Action::HalfPageDown),
+    ("half_page_up", Action::HalfPageUp),
+    ("jump_volume", Action::JumpVolume),
+    ("next_page", Action::NextPage),
+    ("prev_page", Action::PrevPage),
+    ("open_detail", Action::OpenDetail),
+    ("close_detail", Action::CloseDetail),
+    ("open_browser", Action::OpenBrowser),
+    ("star_hg", Action::StarHg),
+    ("export", Action::Export),
+    ("bookmark", Action::Bookmark),
+    ("toggle_seen", Action::ToggleSeen),
+    ("next_unseen", Action::NextUnseen),
+    ("mark_all_seen", Action::MarkAllSeen),
+    ("edit_note", Action::EditNote),
+    ("toggle_readme", Action::ToggleReadme),
+    ("toggle_layout", Action::ToggleLayout),
+];
+
+/// 默认按键，一个动作可以有多个按键，多个键组成的序列用空格分隔
+const DEFAULTS: &[(Scope, Action, &[&str])] = &[
+    (Scope::Search, Action::Search, &["enter"]),
+    (Scope::Search, Action::Cancel, &["esc"]),
+    (Scope::Search, Action::SwitchMode, &["ctrl-j", "ctrl-down"]),
+    (Scope::Search, Action::Help, &["ctrl-h"]),
+    (Scope::Search, Action::PrevHistory, &["up"]),
+    (Scope::Search, Action::NextHistory, &["down"]),
+    (Scope::Search, Action::ReverseSearch, &["ctrl-r"]),
+    (Scope::Search, Action::Complete, &["tab"]),
+    (Scope::Search, Action::CompletePrev, &["backtab"]),
+    (Scope::Search, Action::CursorLeft, &["left"]),
+    (Scope::Search, Action::CursorRight, &["right"]),
+    (Scope::Search, Action::LineStart, &["home", "ctrl-a"]),
+    (Scope::Search, Action::LineEnd, &["end", "ctrl-e"]),
+    (Scope::Search, Action::WordLeft, &["alt-b"]),
+    (Scope::Search, Action::WordRight, &["alt-f"]),
+    (Scope::Search, Action::Backspace, &["backspace"]),
+    (Scope::Search, Action::DeleteChar, &["delete"]),
+    (Scope::Search, Action::DeleteWord, &["ctrl-w"]),
+    (Scope::Search, Action::DeleteToStart, &["ctrl-u"]),
+    (Scope::Search, Action::DeleteToEnd, &["ctrl-k"]),
+    (Scope::View, Action::Cancel, &["esc"]),
+    (Scope::View, Action::SwitchMode, &["ctrl-k", "ctrl-up"]),
+    (Scope::View, Action::Help, &["ctrl-h"]),
+    (Scope::View, Action::Quit, &["q"]),
+    (Scope::View, Action::NextRow, &["j", "down"]),
+    (Scope::View, Action::PrevRow, &["k", "up"]),
+    (Scope::View, Action::NextRows, &["d", "pagedown"]),
+    (Scope::View, Action::PrevRows, &["u", "pageup"]),
+    (Scope::View, Action::FirstRow, &["g g", "home"]),
+    (Scope::View, Action::LastRow, &["G", "end"]),
+    (Scope::View, Action::ScreenTop, &["H"]),
+    (Scope::View, Action::ScreenMiddle, &["M"]),
+    (Scope::View, Action::ScreenBottom, &["L"]),
+    (Scope::View, Action::HalfPageDown, &["ctrl-d"]),
+    (Scope::View, Action::HalfPageUp, &["ctrl-u"]),
+    (Scope::View, Action::JumpVolume, &[":"]),
+    (Scope::View, Action::NextPage, &["l", "right"]),
+    (Scope::View, Action::PrevPage, &["h", "left"]),
+    (Scope::View, Action::OpenDetail, &["o", "ctrl-right"]),
+    (Scope::View, Action::OpenBrowser, &["enter"]),
+    (Scope::View, Action::StarHg, &["s"]),
+    (Scope::View, Action::Export, &["e"]),
+    (Scope::View, Action::Bookmark, &["b"]),
+    (Scope::View, Action::ToggleSeen, &["m"]),
+    (Scope::View, Action::NextUnseen, &["n"]),
+    (Scope::View, Action::MarkAllSeen, &["A"]),
+    (Scope::View, Action::ToggleLayout, &["v"]),
+    (Scope::Detail, Action::Quit, &["q"]),
+    (
+        Scope::Detail,
+        Action::CloseDetail,
+        &["o", "esc", "ctrl-left"],
+    ),
+    (Scope::Detail, Action::OpenBrowser, &["enter"]),
+    (Scope::Detail, Action::Bookmark, &["b"]),
+    (Scope::Detail, Action::EditNote, &["i"]),
+    (Scope::Detail, Action::ToggleReadme, &["r"]),
+    (Scope::Detail, Action::ToggleLayout, &["v"]),
+    (Scope::Detail, Action::NextRow, &["j", "down"]),
+    (Scope::Detail, Action::PrevRow, &["k", "up"]),
+    (Scope::Detail, Action::NextRows, &["d", "pagedown"]),
+    (Scope::Detail, Action::PrevRows, &["u", "pageup"]),
+    (Scope::Detail, Action::FirstRow, &["g g", "home"]),
+    (Scope::Detail, Action::LastRow, &["G", "end"]),
+    (Scope::Detail, Action::HalfPageDown, &["ctrl-d"]),
+    (Scope::Detail, Action::HalfPageUp, &["ctrl-u"]),
+    (Scope::Popup, Action::NextRow, &["j", "down"]),
+    (Scope::Popup, Action::PrevRow, &["k", "up"]),
+    (Scope::Popup, Action::NextRows, &["d", "pagedown"]),
+    (Scope::Popup, Action::PrevRows, &["u", "pageup"]),
+    (Scope::Popup, Action::FirstRow, &["g g", "home"]),
+    (Scope::Popup, Action::LastRow, &["G", "end"]),
+    (Scope::Popup, Action::HalfPageDown, &["ctrl-d"]),
+    (Scope::Popup, Action::HalfPageUp, &["ctrl-u"]),
+];
+
+/// 配置文件中一个动作的按键，可以是一个字符串或者字符串数组
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Keys {
+    fn sequences(&self) -> Vec<&str> {
+        match self {
+            Keys::One(keys) => vec![keys.as_str()],
+            Keys::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    scope: Scope,
+    keys: Vec<Key>,
+    action: Action,
+}
+
+/// 按键解析的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolved {
+    /// 触发了动作，带上按键前输入的数字
+    Action(Action, Option<usize>),
+
+    /// 按键序列或者数字还没输入完
+    Pending,
+
+    /// 没有绑定任何动作的按键
+    Unbound(Key),
+}
+
+/// 按键表，把按键序列翻译成动作，支持 `g g` 这样的组合键和 `5j` 这样的数字前缀
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    /// 已经输入的组合键
+    pending: Vec<Key>,
+    /// 已经输入的数字
+    count: Option<usize>,
+}
+
+impl Keymap {
+    /// 配置文件中的动作替换掉它在各个模式下的默认按键，和配置冲突的其他默认按键也会去掉。
+    /// 可以输入的字符在搜索模式下留给输入框，只在其他模式下生效
+    pub fn new(config: &HashMap<String, Keys>) -> Result<Keymap> {
+        let mut unknown: Vec<&str> = config
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !ACTIONS.iter().any(|(n, _)| n == name))
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort_unstable();
+            bail!("未知的快捷键动作 '{}'", unknown.join("', '"));
+        }
+
+        // 按 ACTIONS 的顺序处理，同样的配置每次报同样的错误
+        let mut bindings: Vec<Binding> = Vec::new();
+        let mut configured = Vec::new();
+        for (name, action) in ACTIONS {
+            let keys = match config.get(*name) {
+                Some(keys) => keys,
+                None => continue,
+            };
+            let scopes: Vec<Scope> = DEFAULTS
+                .iter()
+                .filter(|(_, a, _)| a == action)
+                .map(|(scope, ..)| *scope)
+                .collect();
+            for &scope in &scopes {
+                for sequence in keys.sequences() {
+                    let binding = Binding {
+                        scope,
+                        keys: parse_keys(sequence)?,
+                        action: *action,
+                    };
+                    if scope == Scope::Search && typed_char(binding.keys[0]).is_some() {
+                        if scopes.iter().all(|&s| s == Scope::Search) {
+                            bail!(
+                                "动作 {} 只在搜索模式下使用，不能绑定到可以输入的字符 '{}'",
+                                name,
+                                sequence
+                            );
+                        }
+                        continue;
+                    }
+                    if let Some(other) = bindings.iter().find(|b| conflicts(b, &binding)) {
+                        bail!(
+                            "快捷键 '{}' 和动作 {} 的按键冲突",
+                            sequence,
+                            action_name(other.action)
+                        );
+                    }
+                    bindings.push(binding);
+                    configured.push((scope, *action));
+                }
+            }
+        }
+
+        let user_len = bindings.len();
+        for (scope, action, sequences) in DEFAULTS {
+            if configured.contains(&(*scope, *action)) {
+                continue;
+            }
+            for sequence in *sequences {
+                let binding = Binding {
+                    scope: *scope,
+                    keys: parse_keys(sequence)?,
+                    action: *action,
+                };
+                if !bindings[..user_len].iter().any(|b| conflicts(b, &binding)) {
+                    bindings.push(binding);
+                }
+            }
+        }
+
+        Ok(Keymap {
+            bindings,
+            pending: vec![],
+            count: None,
+        })
+    }
+
+    /// 输入一次按键，组合键没输入完时返回 `Pending`
+    pub fn feed(&mut self, scope: Scope, key: Key) -> Resolved {
+        let key = normalize(key);
+
+        // 搜索模式下数字是输入的内容，其他模式下是动作的次数
+        if let (Scope::View | Scope::Detail, true, (KeyModifiers::NONE, KeyCode::Char(c))) =
+            (scope, self.pending.is_empty(), key)
+        {
+            let digit = c.to_digit(10).filter(|d| *d > 0 || self.count.is_some());
+            if let (Some(digit), false) = (digit, self.is_prefix(scope, &[key])) {
+                let count = self.count.unwrap_or(0);
+                self.count = Some((count * 10 + digit as usize).min(MAX_COUNT));
+                return Resolved::Pending;
+            }
+        }
+
+        self.pending.push(key);
+        if self.is_prefix(scope, &self.pending) {
+            return Resolved::Pending;
+        }
+        let action = self
+            .bindings
+            .iter()
+            .find(|b| b.scope == scope && b.keys == self.pending)
+            .map(|b| b.action);
+        let pending = std::mem::take(&mut self.pending);
+        let count = self.count.take();
+        match action {
+            Some(action) => Resolved::Action(action, count),
+            // 组合键中间按错了，丢掉前面的按键，从这次按键重新开始
+            None if pending.len() > 1 => self.feed(scope, key),
+            None => Resolved::Unbound(key),
+        }
+    }
+
+    /// 丢掉没输入完的组合键和数字
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    /// 是否有更长的按键序列以 `keys` 开头
+    fn is_prefix(&self, scope: Scope, keys: &[Key]) -> bool {
+        self.bindings
+            .iter()
+            .any(|b| b.scope == scope && b.keys.len() > keys.len() && b.keys.starts_with(keys))
+    }
+}
+
+fn action_name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(_, a)| *a == action)
+        .map_or("", |(name, _)| name)
+}
+
+/// 同一模式下一个按键序列是另一个的前缀时，短的那个永远触发不了长的
+fn conflicts(a: &Binding, b: &Binding) -> bool {
+    a.scope == b.scope && (a.keys.starts_with(&b.keys) || b.keys.starts_with(&a.keys))
+}
+
+/// 不带 Ctrl、Alt 的字符，在搜索模式下是要输入的内容
+fn typed_char(key: Key) -> Option<char> {
+    match normalize(key) {
+        (KeyModifiers::NONE, KeyCode::Char(c)) => Some(c),
+        _ => None,
+    }
+}
+
+/// 大写字母和 Shift+Tab 会带上 SHIFT，统一去掉方便比较
+fn normalize((modifiers, code): Key) -> Key {
+    match code {
+        KeyCode::Char(_) | KeyCode::BackTab => (modifiers - KeyModifiers::SHIFT, code),
+        _ => (modifiers, code),
+    }
+}
+
+/// 解析空格分隔的按键序列，如 `g g`、`ctrl-x ctrl-s`
+pub fn parse_keys(desc: &str) -> Result<Vec<Key>> {
+    let keys = desc
+        .split_whitespace()
+        .map(parse_key)
+        .collect::<Result<Vec<Key>>>()?;
+    if keys.is_empty() {
+        bail!("快捷键不能为空");
+    }
+    Ok(keys)
+}
+
+/// 解析按键描述，如 `j`、`G`、`ctrl-n`、`alt-x`、`pagedown`
+pub fn parse_key(desc: &str) -> Result<Key> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = desc;
+    loop {
+        let lower = rest.to_lowercase();
+        if lower.starts_with("ctrl-") {
+            modifiers |= KeyModifiers::CONTROL;
+        } else if lower.starts_with("alt-") {
+            modifiers |= KeyModifiers::ALT;
+        } else {
+            break;
+        }
+        rest = &rest[rest.find('-').unwrap() + 1..];
+    }
+
+    let code = match rest.to_lowercase().as_ref() {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => bail!("无法识别的按键 '{}'", desc),
+            }
+        }
+    };
+
+    Ok((modifiers, code))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(c: char) -> Key {
+        (KeyModifiers::NONE, KeyCode::Char(c))
+    }
+
+    fn keymap(config: &[(&str, Keys)]) -> Keymap {
+        let config = config
+            .iter()
+            .map(|(name, keys)| (name.to_string(), keys.clone()))
+            .collect();
+        Keymap::new(&config).unwrap()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            (KeyModifiers::NONE, KeyCode::Char('G')),
+            parse_key("G").unwrap()
+        );
+        assert_eq!(
+            (KeyModifiers::CONTROL, KeyCode::Char('n')),
+            parse_key("ctrl-n").unwrap()
+        );
+        assert_eq!(
+            (KeyModifiers::NONE, KeyCode::PageDown),
+            parse_key("PageDown").unwrap()
+        );
+        assert!(parse_key("ctrl-").is_err());
+        assert!(parse_key("jj").is_err());
+
+        assert_eq!(vec![key('g'), key('g')], parse_keys(" g  g ").unwrap());
+        assert!(parse_keys(" ").is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let mut keymap = keymap(&[]);
+        assert_eq!(
+            Resolved::Action(Action::NextRow, None),
+            keymap.feed(Scope::View, key('j'))
+        );
+        // 大写字母带着 SHIFT
+        assert_eq!(
+            Resolved::Action(Action::LastRow, None),
+            keymap.feed(Scope::View, (KeyModifiers::SHIFT, KeyCode::Char('G')))
+        );
+        assert_eq!(
+            Resolved::Action(Action::CloseDetail, None),
+            keymap.feed(Scope::Detail, key('o'))
+        );
+        // 搜索模式下字符是输入
+        assert_eq!(
+            Resolved::Unbound(key('j')),
+            keymap.feed(Scope::Search, key('j'))
+        );
+        assert_eq!(
+            Resolved::Action(Action::DeleteToEnd, None),
+            keymap.feed(Scope::Search, (KeyModifiers::CONTROL, KeyCode::Char('k')))
+        );
+    }
+
+    #[test]
+    fn test_chord_and_count() {
+        let mut keymap = keymap(&[]);
+        assert_eq!(Resolved::Pending, keymap.feed(Scope::View, key('g')));
+        assert_eq!(
+            Resolved::Action(Action::FirstRow, None),
+            keymap.feed(Scope::View, key('g'))
+        );
+
+        // 组合键按错了从最后一次按键重新开始
+        assert_eq!(Resolved::Pending, keymap.feed(Scope::View, key('g')));
+        assert_eq!(
+            Resolved::Action(Action::NextRow, None),
+            keymap.feed(Scope::View, key('j'))
+        );
+
+        assert_eq!(Resolved::Pending, keymap.feed(Scope::View, key('1')));
+        assert_eq!(Resolved::Pending, keymap.feed(Scope::View, key('0')));
+        assert_eq!(
+            Resolved::Action(Action::NextRow, Some(10)),
+            keymap.feed(Scope::View, key('j'))
+        );
+        assert_eq!(
+            Resolved::Unbound(key('0')),
+            keymap.feed(Scope::View, key('0'))
+        );
+
+        // 数字太长时停在上限
+        for _ in 0..20 {
+            keymap.feed(Scope::View, key('9'));
+        }
+        assert_eq!(
+            Resolved::Action(Action::HalfPageDown, Some(MAX_COUNT)),
+            keymap.feed(Scope::View, (KeyModifiers::CONTROL, KeyCode::Char('d')))
+        );
+
+        assert_eq!(Resolved::Pending, keymap.feed(Scope::View, key('3')));
+        keymap.reset();
+        assert_eq!(
+            Resolved::Action(Action::PrevRow, None),
+            keymap.feed(Scope::View, key('k'))
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let mut keymap = keymap(&[
+            ("next_row", Keys::One("n".into())),
+            ("prev_row", Keys::Many(vec!["e".into(), "ctrl-p".into()])),
+            ("first_row", Keys::One("ctrl-x ctrl-a".into())),
+        ]);
+
+        // 配置替换默认按键，n 不再是下一个未读
+        assert_eq!(
+            Resolved::Action(Action::NextRow, None),
+            keymap.feed(Scope::View, key('n'))
+        );
+        assert_eq!(
+            Resolved::Unbound(key('j')),
+            keymap.feed(Scope::View, key('j'))
+        );
+        assert_eq!(
+            Resolved::Action(Action::PrevRow, None),
+            keymap.feed(Scope::View, (KeyModifiers::CONTROL, KeyCode::Char('p')))
+        );
+        assert_eq!(
+            Resolved::Pending,
+            keymap.feed(Scope::View, (KeyModifiers::CONTROL, KeyCode::Char('x')))
+        );
+        assert_eq!(
+            Resolved::Action(Action::FirstRow, None),
+            keymap.feed(Scope::View, (KeyModifiers::CONTROL, KeyCode::Char('a')))
+        );
+        assert_eq!(
+            Resolved::Unbound(key('g')),
+            keymap.feed(Scope::View, key('g'))
+        );
+    }
+
+    #[test]
+    fn test_typed_char_in_view() {
+        let ctrl = |c| (KeyModifiers::CONTROL, KeyCode::Char(c));
+        for (name, action, default) in [
+            ("cancel", Action::Cancel, (KeyModifiers::NONE, KeyCode::Esc)),
+            ("help", Action::Help, ctrl('h')),
+            ("switch_mode", Action::SwitchMode, ctrl('j')),
+        ] {
+            let mut keymap = keymap(&[
+                (name, Keys::One("j".into())),
+                ("next_row", Keys::One("n".into())),
+            ]);
+            // 浏览模式下生效，搜索模式下 j 还是输入字符，默认按键也保留
+            assert_eq!(
+                Resolved::Action(action, None),
+                keymap.feed(Scope::View, key('j'))
+            );
+            assert_eq!(
+                Resolved::Unbound(key('j')),
+                keymap.feed(Scope::Search, key('j'))
+            );
+            assert_eq!(
+                Resolved::Action(action, None),
+                keymap.feed(Scope::Search, default)
+            );
+        }
+
+        let mut keymap = keymap(&[("quit", Keys::One("j".into()))]);
+        assert_eq!(
+            Resolved::Action(Action::Quit, None),
+            keymap.feed(Scope::View, key('j'))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = |name: &str, keys: &str| {
+            let mut config = HashMap::new();
+            config.insert(name.to_string(), Keys::One(keys.to_string()));
+            config.insert("next_row".to_string(), Keys::One("n".to_string()));
+            Keymap::new(&config)
+        };
+        assert!(config("fly", "f").is_err());
+        assert!(config("prev_row", "n").is_err());
+        assert!(config("prev_row", "n x").is_err());
+        assert!(config("prev_row", "ctrl-").is_err());
+        assert!(config("prev_row", "p").is_ok());
+
+        // 只在搜索模式下使用的动作不能占用可以输入的字符
+        assert!(config("complete", "j").is_err());
+        assert!(config("complete", "ctrl-n").is_ok());
+
+        // 冲突时总是报 ACTIONS 里靠前的动作
+        for _ in 0..10 {
+            assert_eq!(
+                "快捷键 'n' 和动作 next_row 的按键冲突",
+                config("prev_row", "n").unwrap_err().to_string()
+            );
+        }
+    }
+}
@@ -10,15 +10,30 @@ use cli::parse_args;
 
 mod app;
 mod app_global;
+mod bookmark;
+mod cache;
 mod cli;
+mod command;
 mod config;
 mod draw;
+mod error;
 mod events;
+mod export;
 mod fetch;
+mod history;
+mod index;
+mod keymap;
+mod note;
 mod parse;
+mod readme;
+mod screenshot;
+mod seen;
+mod store;
+mod terminal;
 mod theme;
 mod utils;
 mod widget;
+mod worker;
 
 fn main() -> Result<()> {
     better_panic::install();
@@ -32,7 +47,8 @@ fn main() -> Result<()> {
 
 fn setup_panic_hook() {
     panic::set_hook(Box::new(|panic_info| {
-        // cleanup_terminal();
-        better_panic::Settings::auto().create_panic_handler()(panic_info);
+        terminal::on_panic(|| better_panic::Settings::auto().create_panic_handler()(panic_info));
+        // 任何线程 panic 之后界面都无法继续工作，直接退出
+        std::process::exit(101);
     }));
 }
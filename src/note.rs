@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+use crate::widget::content::Project;
+
+/// 项目的笔记和标签，同时保存项目本身，按标签搜索时不需要联网
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub project: Project,
+
+    pub text: String,
+
+    pub tags: Vec<String>,
+}
+
+/// 所有笔记，项目地址 => 笔记
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Notes {
+    notes: BTreeMap<String, Note>,
+}
+
+impl Notes {
+    pub fn get(&self, url: &str) -> Option<&Note> {
+        self.notes.get(url)
+    }
+
+    /// 保存笔记，笔记和标签都为空时删除
+    pub fn set(&mut self, project: Project, text: &str, tags: &str) {
+        let text = text.trim_end().to_string();
+        let tags = parse_tags(tags);
+        if text.is_empty() && tags.is_empty() {
+            self.notes.remove(&project.url);
+            return;
+        }
+        self.notes.insert(
+            project.url.clone(),
+            Note {
+                project,
+                text,
+                tags,
+            },
+        );
+    }
+
+    /// 带有某个标签的项目的某一页和总页数，标签为空时列出所有打过标签的项目
+    pub fn page(&self, tag: &str, page_no: usize) -> (Vec<Project>, usize) {
+        let tag = tag.trim().to_lowercase();
+        let matched: Vec<Project> = self
+            .notes
+            .values()
+            .filter(|note| {
+                if tag.is_empty() {
+                    !note.tags.is_empty()
+                } else {
+                    note.tags.iter().any(|t| t.to_lowercase() == tag)
+                }
+            })
+            .map(|note| note.project.clone())
+            .collect();
+        utils::paginate(&matched, page_no)
+    }
+}
+
+/// 标签用空格或逗号分隔，可以带 `%` 前缀，去掉重复的
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c.is_whitespace() || c == ',' || c == '，') {
+        let tag = tag.trim_start_matches('%');
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn project(name: &str) -> Project {
+        Project::new(
+            name,
+            72,
+            "C",
+            &format!("https://github.com/{}", name),
+            "",
+            "",
+            "",
+            "",
+        )
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            vec!["cli", "AGPL", "待评估"],
+            parse_tags(" %cli, AGPL，待评估 agpl  ")
+        );
+        assert!(parse_tags(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_notes() {
+        let mut notes = Notes::default();
+        notes.set(project("ncdu"), "看过了，license 是 MIT\n", "cli disk");
+        notes.set(project("black"), "", "Python");
+        notes.set(project("exa"), "只有笔记", "");
+
+        let note = notes.get("https://github.com/ncdu").unwrap();
+        assert_eq!("看过了，license 是 MIT", note.text);
+        assert_eq!(vec!["cli", "disk"], note.tags);
+
+        assert_eq!("black", notes.page("python", 1).0[0].name);
+        assert_eq!(2, notes.page("", 1).0.len());
+        assert!(notes.page("rust", 1).0.is_empty());
+
+        // 清空后删除
+        notes.set(project("ncdu"), " ", "");
+        assert!(notes.get("https://github.com/ncdu").is_none());
+    }
+}
@@ -2,11 +2,15 @@ use std::collections::HashMap;
 
 use nipper::{Document, Selection};
 
-use anyhow::Result;
 use lazy_static::lazy_static;
 use regex::Regex;
 
-use crate::{app::SearchMode, widget::content::Project};
+use crate::{
+    app::SearchMode,
+    error::{HgError, Result},
+    utils::parse_number,
+    widget::content::Project,
+};
 
 lazy_static! {
     static ref RE: Regex = Regex::new(r"<.*?>").unwrap();
@@ -23,13 +27,21 @@ lazy_static! {
 
 const NA: &str = "N/A";
 
-#[derive(Debug, Clone)]
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LastParse {
     Search,
 
-    Volume(String),
+    /// 期数
+    Volume(usize),
 
     Category(String),
+
+    /// 本地收藏夹
+    Favorite,
+
+    /// 按标签找的项目，标签为空时是所有打过标签的项目
+    Tag(String),
 }
 
 pub trait Parser: Sync + Send {
@@ -42,58 +54,56 @@ impl Parser for NormalParser {
     fn parse(&self, html: String) -> Result<(Vec<Project>, LastParse)> {
         let doc = Document::from(&html);
 
-        let projects: Vec<Project> = doc
-            .select(".content-subhead")
-            .iter()
-            .filter_map(|content| {
-                let a = content.select(".project-url");
-                let name = a.text().to_string();
+        let mut projects = Vec::new();
+        for content in doc.select(".content-subhead").iter() {
+            let a = content.select(".project-url");
+            let name = a.text().to_string();
 
-                let url = match a.attr("href") {
-                    Some(href) => href.replace("/periodical/statistics/click/?target=", ""),
-                    _ => {
-                        return None;
-                    }
-                };
+            let url = match a.attr("href") {
+                Some(href) => href.replace("/periodical/statistics/click/?target=", ""),
+                _ => {
+                    continue;
+                }
+            };
 
-                let p = content.next_sibling();
+            let p = expect(content.next_sibling(), "搜索", ".content-subhead + p")?;
 
-                let p_text = p.text();
+            let p_text = p.text();
 
-                let mut desc_iter = p_text
-                    .split('\n')
-                    .map(|s| s.trim())
-                    .filter(|s| !s.is_empty());
+            let mut desc_iter = p_text
+                .split('\n')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty());
 
-                let star = desc_iter.next().unwrap_or(NA).replace("Star ", "");
-                let mut desc = desc_iter.next().unwrap_or(NA);
+            let star = desc_iter.next().unwrap_or(NA).replace("Star ", "");
+            let mut desc = desc_iter.next().unwrap_or(NA);
 
-                if desc == "中文" {
-                    // 再往下找一个
-                    desc = desc_iter.next().unwrap_or(NA);
-                }
+            if desc == "中文" {
+                // 再往下找一个
+                desc = desc_iter.next().unwrap_or(NA);
+            }
 
-                let span = p.next_sibling();
+            let span = expect(p.next_sibling(), "搜索", ".content-subhead + p + span")?;
 
-                let span_text = span.text();
-                let mut span_text_iter = span_text.split('、');
+            let span_text = span.text();
+            let mut span_text_iter = span_text.split('、');
 
-                let volume = span_text_iter.next().unwrap();
+            let volume = span_text_iter.next().unwrap_or_default();
+            let category = span_text_iter
+                .next()
+                .ok_or_else(|| HgError::layout_changed("搜索", "span 期数、类别"))?;
 
-                let category = span_text_iter.next().unwrap();
-
-                Some(Project::new(
-                    name,
-                    volume.to_string(),
-                    category.to_string(),
-                    url,
-                    desc.to_string(),
-                    star,
-                    NA.to_string(),
-                    NA.to_string(),
-                ))
-            })
-            .collect();
+            projects.push(Project::new(
+                name,
+                parse_volume(volume)?,
+                category.to_string(),
+                url,
+                desc.to_string(),
+                star,
+                NA.to_string(),
+                NA.to_string(),
+            ));
+        }
 
         Ok((projects, LastParse::Search))
     }
@@ -103,33 +113,46 @@ pub struct CategoryParser;
 impl Parser for CategoryParser {
     fn parse(&self, html: String) -> Result<(Vec<Project>, LastParse)> {
         let doc = Document::from(&html);
-        let category = doc.select("h1").text().to_string();
-        let projects: Vec<Project> = doc
-            .select("h2.content-subhead")
-            .iter()
-            .map(|pi| {
-                let a = pi.select("a.project-url");
-                let name = a.text().to_string();
-                let url = get_url(&a);
-
-                let p = pi.next_sibling();
-
-                let info_list: Vec<String> = p
-                    .select("i.fa")
-                    .iter()
-                    .map(|i| i.text().to_string())
-                    .collect();
-
-                let volume = info_list[0].clone();
-                let star = info_list[1].clone().replace("Star ", "");
-                let watch = info_list[2].clone().replace("Watch ", "");
-                let fork = info_list[3].clone().replace("Fork ", "");
-
-                let desc = get_desc(&p);
-
-                Project::new(name, volume, category.clone(), url, desc, star, watch, fork)
-            })
-            .collect();
+        let category = select(&doc, "类别", "h1")?.text().to_string();
+        let mut projects = Vec::new();
+        // 翻过最后一页时只有标题没有项目，返回空列表由调用方提示
+        for pi in doc.select("h2.content-subhead").iter() {
+            let a = pi.select("a.project-url");
+            let name = a.text().to_string();
+            let url = get_url(&a, "类别")?;
+
+            let p = expect(pi.next_sibling(), "类别", "h2.content-subhead + p")?;
+
+            let info_list: Vec<String> = p
+                .select("i.fa")
+                .iter()
+                .map(|i| i.text().to_string())
+                .collect();
+
+            let volume = info_list
+                .first()
+                .ok_or_else(|| HgError::layout_changed("类别", "p i.fa 期数"))?;
+            let star = get_counter(&info_list, 1, "Star ");
+            let watch = get_counter(&info_list, 2, "Watch ");
+            let fork = get_counter(&info_list, 3, "Fork ");
+
+            let desc = get_desc(&p);
+            let image = find_image(&p, "h2");
+
+            projects.push(
+                Project::new(
+                    name,
+                    parse_volume(volume)?,
+                    category.clone(),
+                    url,
+                    desc,
+                    star,
+                    watch,
+                    fork,
+                )
+                .with_image(image),
+            );
+        }
         Ok((projects, LastParse::Category(category)))
     }
 }
@@ -139,64 +162,137 @@ impl Parser for VolumeParser {
     fn parse(&self, html: String) -> Result<(Vec<Project>, LastParse)> {
         let doc = Document::from(&html);
 
-        let volume = doc.select("h1").text().to_string();
-        let projects: Vec<Project> = doc
-            .select("a.project-index")
-            .iter()
-            .map(|pi| {
-                let category = find_category(&pi);
-
-                let name = pi.attr("id").unwrap().to_string();
-
-                let a = pi.next_sibling().next_sibling();
-                let url = get_url(&a);
-                let p = a
-                    .next_sibling()
+        let volume = parse_volume(&select(&doc, "月刊", "h1")?.text())?;
+        let mut projects = Vec::new();
+        for pi in select(&doc, "月刊", "a.project-index")?.iter() {
+            let category = find_category(&pi)?;
+
+            let name = pi
+                .attr("id")
+                .ok_or_else(|| HgError::layout_changed("月刊", "a.project-index[id]"))?
+                .to_string();
+
+            let a = expect(
+                pi.next_sibling().next_sibling(),
+                "月刊",
+                "a.project-index ~ a.project-url",
+            )?;
+            let url = get_url(&a, "月刊")?;
+            let p = expect(
+                a.next_sibling()
                     .next_sibling()
                     .next_sibling()
-                    .next_sibling();
-
-                let info_list: Vec<String> = p
-                    .select("i.fa")
-                    .iter()
-                    .map(|i| i.text().to_string())
-                    .collect();
-
-                let star = info_list[0].clone().replace("Star ", "");
-                let watch = info_list[1].clone().replace("Watch ", "");
-                let fork = info_list[2].clone().replace("Fork ", "");
-
-                let desc = get_desc(&p);
-                Project::new(name, volume.clone(), category, url, desc, star, watch, fork)
-            })
-            .collect();
+                    .next_sibling(),
+                "月刊",
+                "a.project-url ~ p",
+            )?;
+
+            let info_list: Vec<String> = p
+                .select("i.fa")
+                .iter()
+                .map(|i| i.text().to_string())
+                .collect();
+
+            let star = get_counter(&info_list, 0, "Star ");
+            let watch = get_counter(&info_list, 1, "Watch ");
+            let fork = get_counter(&info_list, 2, "Fork ");
+
+            let desc = get_desc(&p);
+            let image = find_image(&p, "a.project-index, h2");
+            projects.push(
+                Project::new(name, volume, category, url, desc, star, watch, fork)
+                    .with_image(image),
+            );
+        }
         Ok((projects, LastParse::Volume(volume)))
     }
 }
 
 /// 不停往前找，找到第一个 h2 就是类别
-fn find_category(pi: &Selection) -> String {
-    if pi.is("h2") {
-        return pi.text().to_string();
+fn find_category(pi: &Selection) -> Result<String> {
+    let mut prev = pi.prev_sibling();
+    while prev.exists() {
+        if prev.is("h2") {
+            return Ok(prev.text().to_string());
+        }
+        prev = prev.prev_sibling();
+    }
+    Err(HgError::layout_changed("月刊", "a.project-index 之前的 h2"))
+}
+
+/// 页面中必须存在的元素，找不到说明页面结构变了
+fn select<'a>(doc: &'a Document, page: &'static str, selector: &str) -> Result<Selection<'a>> {
+    let selection = doc.select(selector);
+    if selection.exists() {
+        Ok(selection)
+    } else {
+        Err(HgError::layout_changed(page, selector))
+    }
+}
+
+/// 相邻元素必须是预期的元素，`selector` 用来描述它在页面中的位置
+fn expect<'a>(
+    selection: Selection<'a>,
+    page: &'static str,
+    selector: &str,
+) -> Result<Selection<'a>> {
+    let tag = selector
+        .rsplit(&[' ', '+', '~'][..])
+        .next()
+        .unwrap_or(selector);
+    if selection.exists() && selection.is(tag) {
+        Ok(selection)
+    } else {
+        Err(HgError::layout_changed(page, selector))
     }
-    find_category(&pi.prev_sibling())
 }
 
 fn get_desc(p: &Selection) -> String {
-    let pc = p
-        .html()
-        .split("<br>")
-        .map(|s| s.to_string())
-        .collect::<Vec<String>>();
-    let need_replace = pc[1].trim().replace("</p>", "").replace('\n', "");
-
-    RE.replace_all(&need_replace, "").to_string()
+    let html = p.html();
+    let need_replace = match html.split("<br>").nth(1) {
+        Some(desc) => desc.trim().replace("</p>", "").replace('\n', ""),
+        None => return NA.to_string(),
+    };
+
+    RE.replace_all(&need_replace, "").trim().to_string()
+}
+
+/// 项目介绍之后、下一个项目（`stop`）之前的第一张图片
+fn find_image(p: &Selection, stop: &str) -> Option<String> {
+    let mut next = p.next_sibling();
+    while next.exists() && !next.is(stop) {
+        let img = if next.is("img") {
+            next.clone()
+        } else {
+            next.select("img")
+        };
+        if let Some(src) = img.attr("src").or_else(|| img.attr("data-src")) {
+            return Some(src.trim().to_string()).filter(|src| !src.is_empty());
+        }
+        next = next.next_sibling();
+    }
+    None
+}
+
+fn get_url(a: &Selection, page: &'static str) -> Result<String> {
+    let href = a
+        .attr("href")
+        .ok_or_else(|| HgError::layout_changed(page, "a.project-url[href]"))?;
+
+    Ok(href.replace("/periodical/statistics/click/?target=", ""))
 }
 
-fn get_url(a: &Selection) -> String {
-    a.attr("href")
-        .unwrap()
-        .replace("/periodical/statistics/click/?target=", "")
+/// Star、Watch、Fork 数，没有时返回 N/A
+fn get_counter(info_list: &[String], index: usize, prefix: &str) -> String {
+    info_list
+        .get(index)
+        .map(|info| info.replace(prefix, ""))
+        .unwrap_or_else(|| NA.to_string())
+}
+
+/// 从 "第 72 期" 中取出期数
+fn parse_volume(text: &str) -> Result<usize> {
+    parse_number(text.trim(), 1).ok_or_else(|| HgError::Parse(format!("无效的期数 '{}'", text)))
 }
 
 #[allow(dead_code)]
@@ -213,51 +309,254 @@ pub struct Info {
 }
 
 /// 返回最大期数
-pub fn parse_hg_info(html: String) -> Info {
+pub fn parse_hg_info(html: String) -> Result<Info> {
     let doc = Document::from(&html);
 
-    let text = doc.select(
+    let project_count = parse_hg_number(
+        &doc,
         "body > div.l-content > div.pricing-tables.pure-g > div:nth-child(2) > div > div > span",
-    )
-    .text();
-    let result = text.trim().split(' ').into_iter().collect::<Vec<&str>>();
-    let project_count = result.get(0).unwrap().parse().unwrap();
-
-    let text = doc.select("body > div.l-content > div.pricing-tables.pure-g > div:nth-child(1) > div > div > span").text();
-    let result = text.trim().split(' ').into_iter().collect::<Vec<&str>>();
-    let max_volume = result.get(0).unwrap().parse().unwrap();
+    )?;
+    let max_volume = parse_hg_number(
+        &doc,
+        "body > div.l-content > div.pricing-tables.pure-g > div:nth-child(1) > div > div > span",
+    )?;
+
+    // Star 数只用来展示，页面上没有时不影响其他信息
+    let star = doc
+        .select(
+            "body > div.l-content > div.pricing-tables.pure-g > div:nth-child(3) > div > div > span",
+        )
+        .text()
+        .trim()
+        .to_string();
+    let star = if star.is_empty() {
+        NA.to_string()
+    } else {
+        star
+    };
 
-    Info {
+    Ok(Info {
         max_volume,
         project_count,
-        star: "55.2k".to_string(),
-    }
+        star,
+    })
+}
+
+fn parse_hg_number(doc: &Document, selector: &str) -> Result<usize> {
+    let text = doc.select(selector).text();
+    parse_number(text.trim(), 0).ok_or_else(|| HgError::layout_changed("首页", selector))
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_hg_info_layout_changed() {
+        let result = parse_hg_info("<html><body></body></html>".to_string());
+        assert!(matches!(
+            result,
+            Err(HgError::LayoutChanged { page: "首页", .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_volume() {
+        let result = VolumeParser.parse("<h1>第 N 期</h1>".to_string());
+        assert!(matches!(result, Err(HgError::Parse(_))));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn project(
+        name: &str,
+        volume: usize,
+        category: &str,
+        url: &str,
+        desc: &str,
+        star: &str,
+        watch: &str,
+        fork: &str,
+    ) -> Project {
+        Project::new(name, volume, category, url, desc, star, watch, fork)
+    }
+
+    #[test]
+    fn test_parse_search() {
+        let html = include_str!("../tests/fixtures/search.html");
+        let (projects, last_parse) = NormalParser.parse(html.to_string()).unwrap();
+
+        assert_eq!(LastParse::Search, last_parse);
+        // 没有地址的项目被跳过
+        assert_eq!(
+            vec![
+                project(
+                    "black",
+                    72,
+                    "Python",
+                    "https://github.com/psf/black",
+                    "不妥协的 Python 代码格式化工具。",
+                    "31.2k",
+                    NA,
+                    NA,
+                ),
+                // 跳过 "中文" 标签
+                project(
+                    "Python-100-Days",
+                    30,
+                    "开源书籍",
+                    "https://github.com/jackfrued/Python-100-Days",
+                    "Python 从新手到大师的 100 天学习计划。",
+                    "120k",
+                    NA,
+                    NA,
+                ),
+                project(
+                    "rich",
+                    56,
+                    "Python",
+                    "https://github.com/Textualize/rich",
+                    "在终端输出 富文本 和 漂亮的格式 的 Python 库。",
+                    "40.1k",
+                    NA,
+                    NA,
+                ),
+            ],
+            projects
+        );
+    }
 
-    // #[test]
-    // #[ignore]
-    // fn test_parse_search() {
-    //     let html = include_str!("../search.html");
-    //     let projects = NormalParser.parse(html.to_string()).unwrap();
-    //     assert_eq!(10, projects.0.len());
-    // }
-    //
-    // #[test]
-    // #[ignore]
-    // fn test_parse_volume() {
-    //     let html = include_str!("../volume.html");
-    //     let projects = VolumeParser.parse(html.to_string()).unwrap();
-    //     assert_eq!(26, projects.0.len());
-    // }
-    //
-    // #[test]
-    // #[ignore]
-    // fn test_parse_category() {
-    //     let html = include_str!("../category.html");
-    //     let projects = CategoryParser.parse(html.to_string()).unwrap();
-    //     assert_eq!(10, projects.0.len());
-    // }
+    #[test]
+    fn test_parse_volume() {
+        let html = include_str!("../tests/fixtures/volume.html");
+        let (projects, last_parse) = VolumeParser.parse(html.to_string()).unwrap();
+
+        assert_eq!(LastParse::Volume(72), last_parse);
+        assert_eq!(
+            vec![
+                project(
+                    "ncdu",
+                    72,
+                    "C",
+                    "https://github.com/rofl0r/ncdu",
+                    "命令行磁盘空间分析工具。",
+                    "1.1k",
+                    "30",
+                    "80",
+                )
+                .with_image(Some("https://img.hellogithub.com/i/ncdu.png".into())),
+                // 没有 Watch、Fork 数
+                project(
+                    "tinyhttpd",
+                    72,
+                    "C",
+                    "https://github.com/EZLippi/Tinyhttpd",
+                    "不到 500 行的超轻量 HTTP 服务器，适合学习 网络编程。",
+                    "9.6k",
+                    NA,
+                    NA,
+                ),
+                // 类别标题和项目之间隔着其他元素，图片是懒加载的相对地址
+                project(
+                    "black",
+                    72,
+                    "Python",
+                    "https://github.com/psf/black",
+                    "不妥协的 Python 代码格式化工具，支持 在线试用。",
+                    "31.2k",
+                    "250",
+                    "2k",
+                )
+                .with_image(Some("/static/img/black.png".into())),
+            ],
+            projects
+        );
+    }
+
+    #[test]
+    fn test_parse_category() {
+        let html = include_str!("../tests/fixtures/category.html");
+        let (projects, last_parse) = CategoryParser.parse(html.to_string()).unwrap();
+
+        assert_eq!(LastParse::Category("Rust 项目".to_string()), last_parse);
+        assert_eq!(
+            vec![
+                project(
+                    "ripgrep",
+                    19,
+                    "Rust",
+                    "https://github.com/BurntSushi/ripgrep",
+                    "比 grep 更快的命令行搜索工具。",
+                    "35.6k",
+                    "400",
+                    "1.5k",
+                )
+                .with_image(Some("https://img.hellogithub.com/i/ripgrep.gif".into())),
+                // 没有 Watch、Fork 数，简介里嵌套了 HTML
+                project(
+                    "exa",
+                    72,
+                    "Rust",
+                    "https://github.com/ogham/exa",
+                    "用 Rust 写的 ls 替代品。",
+                    "19.8k",
+                    NA,
+                    NA,
+                ),
+            ],
+            projects
+        );
+    }
+
+    #[test]
+    fn test_parse_category_empty() {
+        let html = include_str!("../tests/fixtures/category_empty.html");
+        let (projects, last_parse) = CategoryParser.parse(html.to_string()).unwrap();
+
+        assert_eq!(LastParse::Category("Rust 项目".to_string()), last_parse);
+        assert!(projects.is_empty());
+    }
+
+    #[test]
+    fn test_parse_volume_without_category() {
+        let html = r#"<h1>第 72 期</h1><a class="project-index" id="ncdu"></a>"#;
+        let result = VolumeParser.parse(html.to_string());
+        assert!(matches!(
+            result,
+            Err(HgError::LayoutChanged { page: "月刊", selector }) if selector.contains("h2")
+        ));
+    }
+
+    #[test]
+    fn test_parse_layout_changed() {
+        let html = include_str!("../tests/fixtures/volume.html").replace("project-index", "index");
+        assert!(matches!(
+            VolumeParser.parse(html),
+            Err(HgError::LayoutChanged { page: "月刊", selector }) if selector == "a.project-index"
+        ));
+
+        let html = include_str!("../tests/fixtures/category.html").replace("<h1>", "<h3>");
+        assert!(matches!(
+            CategoryParser.parse(html),
+            Err(HgError::LayoutChanged { page: "类别", selector }) if selector == "h1"
+        ));
+
+        let html = include_str!("../tests/fixtures/search.html").replace("<span>第", "<div>第");
+        assert!(matches!(
+            NormalParser.parse(html),
+            Err(HgError::LayoutChanged { page: "搜索", .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_hg_info() {
+        let html = include_str!("../tests/fixtures/home.html");
+        let info = parse_hg_info(html.to_string()).unwrap();
+
+        assert_eq!(72, info.max_volume);
+        assert_eq!(2160, info.project_count);
+        assert_eq!("55.2k", info.star);
+
+        let html = html.replace("55.2k", "");
+        assert_eq!(NA, parse_hg_info(html).unwrap().star);
+    }
 }
@@ -0,0 +1,416 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+
+use crate::events::{HGEvent, Notify, NOTIFY};
+use crate::fetch;
+use crate::theme::ThemeStyle;
+use crate::utils;
+
+/// 在后台线程下载 README，完成后通过 `NOTIFY` 通知界面
+pub fn spawn(url: String) {
+    std::thread::spawn(move || {
+        let result = fetch::fetch_readme(url.clone()).map_err(|e| e.to_string());
+        NOTIFY
+            .0
+            .send(HGEvent::NotifyEvent(Notify::Readme(url, result)))
+            .unwrap();
+    });
+}
+
+/// 把 markdown 渲染成带样式的文本行，不处理折行
+pub fn render(markdown: &str, theme: &ThemeStyle) -> Vec<Spans<'static>> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(theme);
+    for event in Parser::new_ext(markdown, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// 按显示宽度把每一行折成不超过 `width` 的几行，保留样式
+pub fn wrap(lines: &[Spans<'static>], width: u16) -> Vec<Spans<'static>> {
+    lines
+        .iter()
+        .flat_map(|line| utils::wrap_spans(line, width as usize))
+        .collect()
+}
+
+/// markdown 事件流到文本行的转换状态
+struct Renderer<'t> {
+    theme: &'t ThemeStyle,
+    lines: Vec<Spans<'static>>,
+    current: Vec<Span<'static>>,
+    /// 嵌套的行内样式，最后一个生效
+    styles: Vec<Style>,
+    /// 嵌套的列表，有序列表记录下一个序号
+    lists: Vec<Option<u64>>,
+    /// 列表项第一行前面的符号，还没输出
+    bullet: Option<String>,
+    /// 引用的层数
+    quotes: usize,
+    in_code_block: bool,
+    in_image: bool,
+    /// 正在输出的链接地址
+    link: Option<String>,
+}
+
+impl<'t> Renderer<'t> {
+    fn new(theme: &'t ThemeStyle) -> Self {
+        Renderer {
+            theme,
+            lines: vec![],
+            current: vec![],
+            styles: vec![Style::default()],
+            lists: vec![],
+            bullet: None,
+            quotes: 0,
+            in_code_block: false,
+            in_image: false,
+            link: None,
+        }
+    }
+
+    fn style(&self) -> Style {
+        *self.styles.last().unwrap()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    /// 每行开头的引用符号、列表缩进
+    fn prefix(&mut self) {
+        if self.quotes > 0 {
+            self.current
+                .push(Span::styled("│ ".repeat(self.quotes), self.theme.tips));
+        }
+        let depth = self.lists.len();
+        match self.bullet.take() {
+            Some(bullet) => self.current.push(Span::styled(
+                format!("{}{}", "  ".repeat(depth.saturating_sub(1)), bullet),
+                self.theme.key,
+            )),
+            None if depth > 0 => self.current.push(Span::raw("  ".repeat(depth))),
+            None => {}
+        }
+    }
+
+    fn push(&mut self, text: impl Into<String>, style: Style) {
+        let text = text.into();
+        if text.is_empty() {
+            return;
+        }
+        if self.current.is_empty() {
+            self.prefix();
+        }
+        self.current.push(Span::styled(text, style));
+    }
+
+    fn text(&mut self, text: &str) {
+        self.push(text, self.style());
+    }
+
+    /// 结束当前行，空行不输出
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            let spans = std::mem::take(&mut self.current);
+            self.lines.push(Spans::from(spans));
+        }
+    }
+
+    /// 段落之间空一行，连续的空行只保留一个
+    fn blank(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Spans::default());
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => {
+                for line in text.lines() {
+                    self.push(format!("  {}", line), self.theme.key);
+                    self.flush();
+                }
+            }
+            Event::Text(text) => self.text(&text),
+            Event::Code(code) => self.push(code.to_string(), self.theme.key),
+            Event::Html(html) => {
+                // 只保留标签之间的文字，README 里常见 <h1 align="center">
+                for line in strip_tags(&html).lines() {
+                    if !line.trim().is_empty() {
+                        self.text(line.trim());
+                        self.flush();
+                    }
+                }
+            }
+            Event::FootnoteReference(name) => self.text(&format!("[^{}]", name)),
+            Event::SoftBreak => self.text(" "),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.blank();
+                self.push("─".repeat(40), self.theme.tips);
+                self.blank();
+            }
+            Event::TaskListMarker(checked) => {
+                self.text(if checked { "[x] " } else { "[ ] " });
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading(level, ..) => {
+                self.blank();
+                self.push_style(self.theme.title.add_modifier(Modifier::BOLD));
+                self.text(&format!("{} ", "#".repeat(level as usize)));
+            }
+            Tag::BlockQuote => {
+                self.flush();
+                self.quotes += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.blank();
+                if let CodeBlockKind::Fenced(lang) = kind {
+                    if !lang.is_empty() {
+                        self.push(format!("  {}", lang), self.theme.tips);
+                        self.flush();
+                    }
+                }
+                self.in_code_block = true;
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.blank();
+                } else {
+                    self.flush();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.bullet = Some(bullet);
+            }
+            Tag::Table(_) => self.blank(),
+            Tag::TableHead => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::TableRow | Tag::TableCell => {}
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link(_, dest, _) => {
+                self.push_style(self.theme.title.add_modifier(Modifier::UNDERLINED));
+                self.link = Some(dest.to_string());
+            }
+            Tag::Image(..) => {
+                self.in_image = true;
+                self.push_style(self.theme.tips);
+                self.text("[图片: ");
+            }
+            Tag::FootnoteDefinition(name) => {
+                self.blank();
+                self.text(&format!("[^{}]: ", name));
+            }
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                if self.lists.is_empty() {
+                    self.blank();
+                } else {
+                    self.flush();
+                }
+            }
+            Tag::Heading(..) => {
+                self.pop_style();
+                self.blank();
+            }
+            Tag::BlockQuote => {
+                self.flush();
+                self.quotes -= 1;
+                if self.quotes == 0 {
+                    self.blank();
+                }
+            }
+            Tag::CodeBlock(_) => {
+                self.in_code_block = false;
+                self.blank();
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank();
+                } else {
+                    self.flush();
+                }
+            }
+            Tag::Item => self.flush(),
+            Tag::Table(_) => self.blank(),
+            Tag::TableHead => {
+                self.pop_style();
+                self.flush();
+            }
+            Tag::TableRow => self.flush(),
+            Tag::TableCell => self.push(" │ ", self.theme.tips),
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => self.pop_style(),
+            Tag::Link(..) => {
+                self.pop_style();
+                // 链接在图片里面时（徽章）不再重复地址
+                if let Some(dest) = self.link.take().filter(|_| !self.in_image) {
+                    if dest.starts_with("http") {
+                        self.push(format!(" <{}>", dest), self.theme.tips);
+                    }
+                }
+            }
+            Tag::Image(..) => {
+                self.text("]");
+                self.pop_style();
+                self.in_image = false;
+            }
+            Tag::FootnoteDefinition(_) => self.blank(),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Spans<'static>> {
+        self.flush();
+        while self.lines.last().is_some_and(|line| line.width() == 0) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+}
+
+/// 去掉 html 标签，只留下文字
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use tui::style::Color;
+
+    use super::*;
+    use crate::utils::texts;
+
+    fn theme() -> ThemeStyle {
+        ThemeStyle {
+            background: Style::default(),
+            title: Style::default().fg(Color::Blue),
+            tips: Style::default().fg(Color::Gray),
+            selected: Style::default(),
+            text: Style::default(),
+            key: Style::default().fg(Color::Yellow),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let markdown = "# ncdu\n\n\
+            A **disk** usage analyzer with `ncurses`.\n\n\
+            ## Install\n\n\
+            - apt\n  - `apt install ncdu`\n- [brew](https://brew.sh)\n\n\
+            1. one\n2. two\n\n\
+            ```sh\nmake\nmake install\n```\n\n\
+            > quoted\n\n\
+            ---\n\n\
+            ![badge](https://img.shields.io/x.svg)\n\n\
+            <h3 align=\"center\">Centered</h3>\n";
+        let lines = render(markdown, &theme());
+        assert_eq!(
+            vec![
+                "# ncdu",
+                "",
+                "A disk usage analyzer with ncurses.",
+                "",
+                "## Install",
+                "",
+                "• apt",
+                "  • apt install ncdu",
+                "• brew <https://brew.sh>",
+                "",
+                "1. one",
+                "2. two",
+                "",
+                "  sh",
+                "  make",
+                "  make install",
+                "",
+                "│ quoted",
+                "",
+                &"─".repeat(40),
+                "",
+                "[图片: badge]",
+                "",
+                "Centered",
+            ],
+            texts(&lines)
+        );
+
+        let heading = &lines[0].0[0];
+        assert_eq!(Some(Color::Blue), heading.style.fg);
+        assert!(heading.style.add_modifier.contains(Modifier::BOLD));
+        let strong = &lines[2].0[1];
+        assert_eq!("disk", strong.content);
+        assert!(strong.style.add_modifier.contains(Modifier::BOLD));
+        let link = &lines[8].0[1];
+        assert_eq!("brew", link.content);
+        assert!(link.style.add_modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(Some(Color::Yellow), lines[14].0[0].style.fg);
+    }
+
+    #[test]
+    fn test_render_table() {
+        let lines = render("| a | b |\n|---|---|\n| 1 | 2 |\n\n- [x] done", &theme());
+        assert_eq!(
+            vec!["a │ b │ ", "1 │ 2 │ ", "", "• [x] done"],
+            texts(&lines)
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let lines = vec![
+            Spans::from(vec![
+                Span::raw("ab"),
+                Span::styled("cdef", Style::default().fg(Color::Red)),
+            ]),
+            Spans::default(),
+            Spans::from("中文字"),
+        ];
+        let wrapped = wrap(&lines, 4);
+        assert_eq!(vec!["abcd", "ef", "", "中文", "字"], texts(&wrapped));
+        assert_eq!(Some(Color::Red), wrapped[1].0[0].style.fg);
+    }
+}
@@ -0,0 +1,544 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Cursor, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use cached::proc_macro::cached;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossterm::{cursor::MoveTo, queue};
+use image::{DynamicImage, GenericImageView, ImageOutputFormat};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use tui::layout::Rect;
+
+use crate::events::{HGEvent, Notify, NOTIFY};
+use crate::fetch;
+use crate::terminal;
+
+/// 解码后图片的最大宽高，再大的截图在终端里也看不出区别
+const MAX_SIZE: u32 = 800;
+
+/// 分栏预览时选中的项目停留这么久才下载截图
+const PREVIEW_DELAY: Duration = Duration::from_millis(300);
+
+lazy_static! {
+    /// 预览的截图地址，由一个线程合并连续的切换
+    static ref PREVIEW: Sender<String> = {
+        let (sender, receiver) = unbounded();
+        std::thread::spawn(move || debounce(receiver, PREVIEW_DELAY, spawn));
+        sender
+    };
+}
+
+/// kitty 协议每段传输的 base64 长度上限
+const KITTY_CHUNK: usize = 4096;
+
+/// 截图的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// 根据终端自动选择
+    #[default]
+    Auto,
+
+    /// 用半格字符 `▀` 拼出图片，支持真彩色的终端都能用
+    HalfBlocks,
+
+    /// kitty 图形协议，kitty、WezTerm、Ghostty 支持
+    Kitty,
+
+    /// sixel，foot、mlterm、开启了 sixel 的 xterm 等支持
+    Sixel,
+}
+
+impl Protocol {
+    /// 把 auto 换成当前终端支持的方式
+    pub fn resolve(self) -> Protocol {
+        match self {
+            Protocol::Auto => detect(|key| std::env::var(key).ok()),
+            protocol => protocol,
+        }
+    }
+}
+
+/// 根据环境变量判断终端支持的图形协议，tmux、screen 不会转发图片，一律用半格字符
+fn detect(var: impl Fn(&str) -> Option<String>) -> Protocol {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+        Protocol::HalfBlocks
+    } else if var("KITTY_WINDOW_ID").is_some()
+        || term.contains("kitty")
+        || matches!(program.as_str(), "WezTerm" | "ghostty")
+    {
+        Protocol::Kitty
+    } else if term.contains("sixel")
+        || term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || term == "contour"
+    {
+        Protocol::Sixel
+    } else {
+        Protocol::HalfBlocks
+    }
+}
+
+/// 下载并解码截图，解码后的图片缓存在内存里，反复打开同一个项目不用重新解码
+#[cached(size = 32, result = true)]
+fn load(src: String) -> Result<Arc<DynamicImage>> {
+    let bytes = fetch::fetch_image(&src)?;
+    let image = image::load_from_memory(&bytes).context("无法识别的图片格式")?;
+    let image = if image.width() > MAX_SIZE || image.height() > MAX_SIZE {
+        image.thumbnail(MAX_SIZE, MAX_SIZE)
+    } else {
+        image
+    };
+    Ok(Arc::new(image))
+}
+
+/// 在后台线程加载截图，完成后通过 `NOTIFY` 通知界面
+pub fn spawn(src: String) {
+    std::thread::spawn(move || {
+        let result = load(src.clone()).map_err(|e| format!("{:#}", e));
+        NOTIFY
+            .0
+            .send(HGEvent::NotifyEvent(Notify::Image(src, result)))
+            .unwrap();
+    });
+}
+
+/// 分栏时跟着选中的项目预览截图。按住 j 快速移动时不下载经过的每一张，
+/// 选中的项目停下来一段时间后才加载最后一张
+pub fn preview(src: String) {
+    PREVIEW.send(src).unwrap();
+}
+
+/// 收到地址后等 `delay`，期间又收到新地址就重新等，最后只对停下来时的地址调用 `load`
+fn debounce(receiver: Receiver<String>, delay: Duration, load: impl Fn(String)) {
+    while let Ok(mut src) = receiver.recv() {
+        while let Ok(next) = receiver.recv_timeout(delay) {
+            src = next;
+        }
+        load(src);
+    }
+}
+
+/// 保持宽高比，算出图片在 `area` 里占的格子，水平居中。`cell` 是一个格子的像素宽高
+pub fn fit((width, height): (u32, u32), area: Rect, (cell_w, cell_h): (u16, u16)) -> Rect {
+    let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+    let scale = (area.width as f64 * cell_w as f64 / width)
+        .min(area.height as f64 * cell_h as f64 / height);
+    let cols = ((width * scale / cell_w as f64).round() as u16).clamp(1, area.width.max(1));
+    let rows = ((height * scale / cell_h as f64).round() as u16).clamp(1, area.height.max(1));
+    Rect::new(
+        area.x + area.width.saturating_sub(cols) / 2,
+        area.y,
+        cols,
+        rows,
+    )
+}
+
+/// 在后台编码图片，测试时换成不开线程的
+type EncodeFn = fn(Protocol, String, Arc<DynamicImage>, Rect);
+
+/// 用 kitty、sixel 协议显示图片。tui 只管字符，图片在每次绘制之后直接写到终端
+#[derive(Debug)]
+pub struct Graphics {
+    protocol: Protocol,
+
+    /// 正在显示的图片地址和位置
+    shown: Option<(String, Rect)>,
+
+    /// 最近编码好的图片地址、位置和要输出的内容
+    encoded: Option<(String, Rect, Vec<u8>)>,
+
+    /// 正在后台编码的图片地址和位置，编码完成前不重复编码
+    encoding: Option<(String, Rect)>,
+
+    spawn_encode: EncodeFn,
+}
+
+impl Graphics {
+    /// 终端只能用半格字符时返回 None，由详情页自己画
+    pub fn new(protocol: Protocol) -> Option<Graphics> {
+        match protocol.resolve() {
+            Protocol::Auto | Protocol::HalfBlocks => None,
+            protocol => Some(Graphics {
+                protocol,
+                shown: None,
+                encoded: None,
+                encoding: None,
+                spawn_encode,
+            }),
+        }
+    }
+
+    /// 在 `area` 上显示图片，`image` 为 None 时隐藏，和正在显示的一样时什么都不做。
+    /// 编码要好几百毫秒，不能在绘制时做，还没编码好时交给后台线程，编码完成后通过
+    /// `encoded` 传回来再重绘。
+    /// sixel 画上去的图片只能靠重绘整个屏幕擦掉，返回 true 时需要清屏重绘后再调用一次
+    pub fn show<W: Write>(
+        &mut self,
+        out: &mut W,
+        image: Option<(&str, &Arc<DynamicImage>, Rect)>,
+    ) -> io::Result<bool> {
+        let target = image.map(|(src, _, area)| (src.to_string(), area));
+        if self.shown == target {
+            return Ok(false);
+        }
+
+        if self.shown.take().is_some() {
+            if self.protocol == Protocol::Sixel {
+                return Ok(true);
+            }
+            write!(out, "\x1b_Ga=d,q=2\x1b\\")?;
+        }
+
+        if let (Some((src, image, area)), Some(target)) = (image, target) {
+            match &self.encoded {
+                Some((encoded_src, encoded_area, data))
+                    if encoded_src == src && *encoded_area == area =>
+                {
+                    out.write_all(data)?;
+                    self.shown = Some(target);
+                }
+                _ if self.encoding.as_ref() != Some(&target) => {
+                    (self.spawn_encode)(self.protocol, src.to_string(), image.clone(), area);
+                    self.encoding = Some(target);
+                }
+                _ => {}
+            }
+        }
+        out.flush()?;
+        Ok(false)
+    }
+
+    /// 后台编码完成，已经换了图片或者位置时丢弃
+    pub fn encoded(&mut self, src: String, area: Rect, data: Vec<u8>) {
+        if self.encoding.as_ref() == Some(&(src.clone(), area)) {
+            self.encoding = None;
+            self.encoded = Some((src, area, data));
+        }
+    }
+}
+
+/// 在后台线程编码图片，完成后通过 `NOTIFY` 通知界面。编码失败时不显示截图
+fn spawn_encode(protocol: Protocol, src: String, image: Arc<DynamicImage>, area: Rect) {
+    std::thread::spawn(move || {
+        if let Ok(data) = encode(protocol, &image, area, terminal::cell_size()) {
+            NOTIFY
+                .0
+                .send(HGEvent::NotifyEvent(Notify::Graphics(src, area, data)))
+                .unwrap();
+        }
+    });
+}
+
+/// 按协议编码在 `area` 里显示的图片，包括移动光标，`cell` 是一个格子的像素宽高
+fn encode(
+    protocol: Protocol,
+    image: &DynamicImage,
+    area: Rect,
+    cell: (u16, u16),
+) -> io::Result<Vec<u8>> {
+    let rect = fit(image.dimensions(), area, cell);
+    let (width, height) = ((rect.width * cell.0) as u32, (rect.height * cell.1) as u32);
+    let text = match protocol {
+        Protocol::Kitty => kitty(&image.thumbnail(width, height), rect)?,
+        _ => sixel(&image.thumbnail_exact(width, height)),
+    };
+    let mut out = Vec::new();
+    queue!(out, MoveTo(rect.x, rect.y))?;
+    out.extend_from_slice(text.as_bytes());
+    Ok(out)
+}
+
+/// kitty 图形协议，图片用 PNG 分段传输，由终端缩放到 `rect` 的格子里
+fn kitty(image: &DynamicImage, rect: Rect) -> io::Result<String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(io::Error::other)?;
+    let data = STANDARD.encode(png);
+
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            // q=2 不要回复，回复会被当成按键读进来；C=1 不移动光标
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};",
+                rect.width, rect.height, more
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={};", more);
+        }
+        out.push_str(chunk);
+        out.push_str("\x1b\\");
+    }
+    Ok(out)
+}
+
+/// sixel 编码，颜色量化到 6x6x6 的调色板，透明的像素不画
+fn sixel(image: &DynamicImage) -> String {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let mut out = format!("\x1bP0;1q\"1;1;{};{}", width, height);
+    for i in 0..216 {
+        let _ = write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            i / 36 * 20,
+            i / 6 % 6 * 20,
+            i % 6 * 20
+        );
+    }
+
+    let color = |x: u32, y: u32| {
+        let pixel = image.get_pixel(x, y);
+        let level = |c: u8| (c as u32 * 5 + 127) / 255;
+        (pixel[3] >= 128).then(|| level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]))
+    };
+
+    // 每 6 行像素一个条带，条带里每种颜色输出一遍。先扫一遍条带，
+    // 记下每种颜色在每一列占了哪几行
+    for top in (0..height).step_by(6) {
+        let mut bitmaps: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+        for y in top..(top + 6).min(height) {
+            for x in 0..width {
+                if let Some(c) = color(x, y) {
+                    bitmaps.entry(c).or_insert_with(|| vec![0; width as usize])[x as usize] |=
+                        1 << (y - top);
+                }
+            }
+        }
+
+        for (c, bits) in bitmaps {
+            let _ = write!(out, "#{}", c);
+            push_runs(&mut out, bits.into_iter().map(|bits| (63 + bits) as char));
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// 连续相同的字符用 `!{次数}{字符}` 压缩
+fn push_runs(out: &mut String, chars: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |out: &mut String, (c, n): (char, usize)| {
+        if n > 3 {
+            let _ = write!(out, "!{}{}", n, c);
+        } else {
+            out.extend(std::iter::repeat_n(c, n));
+        }
+    };
+    for c in chars {
+        run = match run {
+            Some((last, n)) if last == c => Some((last, n + 1)),
+            Some(last) => {
+                flush(out, last);
+                Some((c, 1))
+            }
+            None => Some((c, 1)),
+        };
+    }
+    if let Some(last) = run {
+        flush(out, last);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_debounce() {
+        let (sender, receiver) = unbounded();
+        let (loaded_sender, loaded) = unbounded();
+        std::thread::spawn(move || {
+            debounce(receiver, Duration::from_millis(50), move |src| {
+                loaded_sender.send(src).unwrap()
+            })
+        });
+
+        // 连续切换只加载最后一张
+        for src in ["ncdu.png", "black.png", "exa.png"] {
+            sender.send(src.to_string()).unwrap();
+        }
+        let timeout = Duration::from_secs(1);
+        assert_eq!(Ok("exa.png".to_string()), loaded.recv_timeout(timeout));
+        sender.send("rich.png".to_string()).unwrap();
+        assert_eq!(Ok("rich.png".to_string()), loaded.recv_timeout(timeout));
+        assert!(loaded.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Protocol::HalfBlocks,
+            detect(env(&[("TERM", "xterm-256color")]))
+        );
+        assert_eq!(Protocol::Kitty, detect(env(&[("TERM", "xterm-kitty")])));
+        assert_eq!(
+            Protocol::Kitty,
+            detect(env(&[
+                ("TERM", "xterm-256color"),
+                ("TERM_PROGRAM", "WezTerm")
+            ]))
+        );
+        assert_eq!(Protocol::Sixel, detect(env(&[("TERM", "foot")])));
+        assert_eq!(
+            Protocol::HalfBlocks,
+            detect(env(&[
+                ("TERM", "xterm-kitty"),
+                ("TMUX", "/tmp/tmux-0/default")
+            ]))
+        );
+        assert_eq!(Protocol::Kitty, Protocol::Kitty.resolve());
+    }
+
+    #[test]
+    fn test_fit() {
+        let area = Rect::new(10, 5, 40, 10);
+        // 正方形的图片，字符是 1:2 的，宽度是高度的两倍
+        assert_eq!(Rect::new(20, 5, 20, 10), fit((100, 100), area, (1, 2)));
+        // 很宽的图片占满宽度
+        assert_eq!(Rect::new(10, 5, 40, 1), fit((400, 10), area, (1, 2)));
+        // 很高的图片也至少一列
+        assert_eq!(Rect::new(29, 5, 1, 10), fit((1, 1000), area, (8, 16)));
+        assert_eq!(
+            Rect::new(10, 5, 1, 1),
+            fit((0, 0), Rect::new(10, 5, 0, 0), (8, 16))
+        );
+    }
+
+    #[test]
+    fn test_sixel() {
+        // 2x7：第一列红色，第二列上面透明，最后一行蓝色
+        let mut image = RgbaImage::from_pixel(2, 7, Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([0, 0, 0, 0]));
+        for y in 1..6 {
+            image.put_pixel(1, y, Rgba([255, 0, 0, 255]));
+        }
+        image.put_pixel(0, 6, Rgba([0, 0, 255, 255]));
+        image.put_pixel(1, 6, Rgba([0, 0, 255, 255]));
+        let text = sixel(&DynamicImage::ImageRgba8(image));
+
+        assert!(text.starts_with("\x1bP0;1q\"1;1;2;7#0;2;0;0;0"));
+        assert!(text.contains("#180;2;100;0;0"));
+        // 红色是 180 号，第一列 6 个像素都是红色，第二列少了最上面一个
+        assert!(text.contains("#180~}$-"));
+        // 蓝色是 5 号，在第二个条带的第一行
+        assert!(text.ends_with("#5@@$-\x1b\\"));
+    }
+
+    #[test]
+    fn test_push_runs() {
+        let mut out = String::new();
+        push_runs(&mut out, "aaabbbbbc".chars());
+        assert_eq!("aaa!5bc", out);
+    }
+
+    #[test]
+    fn test_kitty() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, (x * y) as u8, 255])
+        }));
+        let text = kitty(&image, Rect::new(0, 0, 8, 4)).unwrap();
+        assert!(text.starts_with("\x1b_Ga=T,f=100,q=2,C=1,c=8,r=4,m=1;iVBORw0KGgo"));
+        // 分段传输，最后一段 m=0
+        assert!(text.contains("\x1b\\\x1b_Gm=1;"));
+        assert!(text.contains("\x1b\\\x1b_Gm=0;"));
+        assert!(text.ends_with("\x1b\\"));
+
+        let small = DynamicImage::ImageRgba8(RgbaImage::new(1, 1));
+        let text = kitty(&small, Rect::new(0, 0, 1, 1)).unwrap();
+        assert!(text.starts_with("\x1b_Ga=T,f=100,q=2,C=1,c=1,r=1,m=0;"));
+        assert_eq!(1, text.matches("\x1b_G").count());
+    }
+
+    fn new_graphics(protocol: Protocol) -> Graphics {
+        Graphics {
+            protocol,
+            shown: None,
+            encoded: None,
+            encoding: None,
+            spawn_encode: |_, _, _, _| {},
+        }
+    }
+
+    #[test]
+    fn test_show() {
+        let mut graphics = new_graphics(Protocol::Kitty);
+        let image = Arc::new(DynamicImage::ImageRgba8(RgbaImage::new(4, 4)));
+        let area = Rect::new(3, 2, 10, 5);
+
+        // 还没编码好时先不显示，等后台编码
+        let mut out = Vec::new();
+        assert!(!graphics
+            .show(&mut out, Some(("a.png", &image, area)))
+            .unwrap());
+        assert!(out.is_empty());
+        assert_eq!(Some(("a.png".to_string(), area)), graphics.encoding);
+
+        // 位置已经变了的编码结果丢弃
+        let data = encode(Protocol::Kitty, &image, area, (1, 2)).unwrap();
+        graphics.encoded("a.png".into(), Rect::new(0, 0, 10, 5), data.clone());
+        assert!(graphics.encoded.is_none());
+        graphics.encoded("a.png".into(), area, data);
+        graphics
+            .show(&mut out, Some(("a.png", &image, area)))
+            .unwrap();
+        let text = String::from_utf8_lossy(&out).to_string();
+        // 移动到居中后的位置再输出
+        assert!(text.starts_with("\x1b[3;4H\x1b_Ga=T"));
+
+        // 没有变化时不重复输出
+        out.clear();
+        graphics
+            .show(&mut out, Some(("a.png", &image, area)))
+            .unwrap();
+        assert!(out.is_empty());
+
+        // 隐藏时删除图片，再显示时不用重新编码
+        graphics.show(&mut out, None).unwrap();
+        assert_eq!("\x1b_Ga=d,q=2\x1b\\", String::from_utf8_lossy(&out));
+        out.clear();
+        graphics
+            .show(&mut out, Some(("a.png", &image, area)))
+            .unwrap();
+        assert!(String::from_utf8_lossy(&out).contains("\x1b_Ga=T"));
+
+        // sixel 需要整屏重绘
+        let mut graphics = new_graphics(Protocol::Sixel);
+        graphics.shown = Some(("a.png".into(), area));
+        assert!(graphics.show(&mut Vec::new(), None).unwrap());
+        assert!(!graphics.show(&mut Vec::new(), None).unwrap());
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// 看过的项目地址，打开过详情或者浏览器就算看过
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Seen {
+    urls: BTreeSet<String>,
+}
+
+impl Seen {
+    pub fn is_seen(&self, url: &str) -> bool {
+        self.urls.contains(url)
+    }
+
+    pub fn mark(&mut self, url: &str) {
+        self.urls.insert(url.to_string());
+    }
+
+    /// 切换已读状态，返回切换后是否已读
+    pub fn toggle(&mut self, url: &str) -> bool {
+        if self.urls.remove(url) {
+            return false;
+        }
+        self.mark(url);
+        true
+    }
+
+    /// 全部标记为已读，返回新标记的个数
+    pub fn mark_all<'a>(&mut self, urls: impl IntoIterator<Item = &'a str>) -> usize {
+        urls.into_iter()
+            .filter(|url| self.urls.insert(url.to_string()))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seen() {
+        let mut seen = Seen::default();
+        let ncdu = "https://github.com/rofl0r/ncdu";
+        let black = "https://github.com/psf/black";
+
+        assert!(!seen.is_seen(ncdu));
+        assert!(seen.toggle(ncdu));
+        assert!(seen.is_seen(ncdu));
+        assert!(!seen.toggle(ncdu));
+        assert!(!seen.is_seen(ncdu));
+
+        seen.mark(black);
+        assert_eq!(1, seen.mark_all([ncdu, black]));
+        assert!(seen.is_seen(ncdu) && seen.is_seen(black));
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// 保存在数据目录下的一个 JSON 文件，收藏、已读记录等都用它持久化
+#[derive(Debug)]
+pub struct Store<T> {
+    path: PathBuf,
+    data: T,
+}
+
+impl<T> Store<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    /// 打开文件，不存在时使用默认值，第一次修改时才创建
+    pub fn open(path: impl Into<PathBuf>) -> Result<Store<T>> {
+        let path = path.into();
+        let data = if path.exists() {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("无法读取 {}", path.display()))?;
+            serde_json::from_str(&text)
+                .with_context(|| format!("{} 格式有误，可以删除后重试", path.display()))?
+        } else {
+            T::default()
+        };
+        Ok(Store { path, data })
+    }
+
+    pub fn get(&self) -> &T {
+        &self.data
+    }
+
+    /// 修改数据并立即写回文件
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let result = f(&mut self.data);
+        self.save()?;
+        Ok(result)
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // 先写临时文件再重命名，避免写到一半退出时把原文件弄坏
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&self.data)?)
+            .with_context(|| format!("无法写入 {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("无法写入 {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_and_update() {
+        let dir = std::env::temp_dir().join(format!("hgtui-store-{}", std::process::id()));
+        let path = dir.join("numbers.json");
+
+        let mut store: Store<Vec<usize>> = Store::open(&path).unwrap();
+        assert!(store.get().is_empty());
+        assert!(!path.exists());
+
+        let len = store.update(|numbers| {
+            numbers.push(72);
+            numbers.len()
+        });
+        assert_eq!(1, len.unwrap());
+
+        let store: Store<Vec<usize>> = Store::open(&path).unwrap();
+        assert_eq!(&vec![72], store.get());
+
+        fs::write(&path, "not json").unwrap();
+        assert!(Store::<Vec<usize>>::open(&path).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
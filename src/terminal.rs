@@ -0,0 +1,289 @@
+use std::io::{self, Stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{backend::CrosstermBackend, Terminal};
+
+/// 全局唯一的终端状态，panic、信号、正常退出都通过它来还原终端
+static GUARD: TerminalGuard = TerminalGuard::new(enable_raw_mode, disable_raw_mode);
+
+type RawModeFn = fn() -> crossterm::Result<()>;
+
+/// 记录终端是否处于 raw mode + 备用屏幕，保证只还原一次
+pub struct TerminalGuard {
+    active: AtomicBool,
+    enable_raw: RawModeFn,
+    disable_raw: RawModeFn,
+}
+
+impl TerminalGuard {
+    pub const fn new(enable_raw: RawModeFn, disable_raw: RawModeFn) -> TerminalGuard {
+        TerminalGuard {
+            active: AtomicBool::new(false),
+            enable_raw,
+            disable_raw,
+        }
+    }
+
+    /// 进入 raw mode、备用屏幕，`mouse` 为真时开启鼠标捕获
+    pub fn enter<W: Write>(&self, out: &mut W, mouse: bool) -> io::Result<()> {
+        (self.enable_raw)()?;
+        self.active.store(true, Ordering::SeqCst);
+        execute!(out, EnterAlternateScreen)?;
+        if mouse {
+            execute!(out, EnableMouseCapture)?;
+        }
+        Ok(())
+    }
+
+    /// 还原终端，重复调用时什么都不做
+    pub fn restore<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if !self.active.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        // 尽量都执行一遍，只返回第一个错误
+        let raw = (self.disable_raw)();
+        let screen = execute!(out, LeaveAlternateScreen, DisableMouseCapture, Show);
+        raw?;
+        screen?;
+        Ok(())
+    }
+
+    /// 进入界面后调用 `create` 创建要用的终端，创建失败时还原
+    pub fn open<W: Write, T>(
+        &self,
+        out: &mut W,
+        mouse: bool,
+        create: impl FnOnce() -> io::Result<T>,
+    ) -> io::Result<T> {
+        let result = self.enter(out, mouse).and_then(|_| create());
+        if result.is_err() {
+            let _ = self.restore(out);
+        }
+        result
+    }
+
+    /// panic 时先还原终端，再调用 `report` 打印错误信息，否则信息会留在备用屏幕里
+    pub fn on_panic<W: Write>(&self, out: &mut W, report: impl FnOnce()) {
+        let _ = self.restore(out);
+        report();
+    }
+}
+
+/// 进入 raw mode、备用屏幕并创建 tui 的终端，失败时终端保持原样
+pub fn open(mouse: bool) -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    GUARD.open(&mut io::stdout(), mouse, || {
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        terminal.clear()?;
+        Ok(terminal)
+    })
+}
+
+pub fn restore() {
+    // 已经在退出的路上了，还原失败也没有更好的办法
+    let _ = GUARD.restore(&mut io::stdout());
+}
+
+/// panic hook 里使用，还原终端后再打印 panic 信息
+pub fn on_panic(report: impl FnOnce()) {
+    GUARD.on_panic(&mut io::stdout(), report);
+}
+
+/// 拿不到终端字符的像素大小时使用的默认值
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// 终端里一个字符的像素宽高，sixel 需要按像素画图
+#[cfg(unix)]
+pub fn cell_size() -> (u16, u16) {
+    // SAFETY: winsize 是纯数据结构，ioctl 只往里面写
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    if !ok || size.ws_col == 0 || size.ws_row == 0 {
+        return DEFAULT_CELL_SIZE;
+    }
+    match (size.ws_xpixel / size.ws_col, size.ws_ypixel / size.ws_row) {
+        (0, _) | (_, 0) => DEFAULT_CELL_SIZE,
+        cell => cell,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn cell_size() -> (u16, u16) {
+    DEFAULT_CELL_SIZE
+}
+
+/// 收到 SIGTERM、SIGINT、SIGHUP 时还原终端再退出
+#[cfg(unix)]
+pub fn handle_signals() -> io::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            restore();
+            std::process::exit(128 + signal);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn handle_signals() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static RAW: AtomicBool = AtomicBool::new(false);
+
+    fn fake_enable_raw() -> crossterm::Result<()> {
+        RAW.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn fake_disable_raw() -> crossterm::Result<()> {
+        RAW.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn noop() -> crossterm::Result<()> {
+        Ok(())
+    }
+
+    #[test]
+    fn test_enter_and_restore() {
+        let guard = TerminalGuard::new(fake_enable_raw, fake_disable_raw);
+        let mut out = Vec::new();
+
+        guard.enter(&mut out, true).unwrap();
+        assert!(RAW.load(Ordering::SeqCst));
+        let entered = String::from_utf8_lossy(&out).to_string();
+        assert!(entered.contains("\x1b[?1049h"));
+        assert!(entered.contains("\x1b[?1000h"));
+
+        out.clear();
+        guard.restore(&mut out).unwrap();
+        assert!(!RAW.load(Ordering::SeqCst));
+        let restored = String::from_utf8_lossy(&out).to_string();
+        // 离开备用屏幕、关闭鼠标捕获、显示光标
+        assert!(restored.contains("\x1b[?1049l"));
+        assert!(restored.contains("\x1b[?1000l"));
+        assert!(restored.contains("\x1b[?25h"));
+
+        // 再次还原什么都不做
+        out.clear();
+        guard.restore(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_enter_without_mouse() {
+        let guard = TerminalGuard::new(noop, noop);
+        let mut out = Vec::new();
+        guard.enter(&mut out, false).unwrap();
+        let entered = String::from_utf8_lossy(&out).to_string();
+        assert!(entered.contains("\x1b[?1049h"));
+        assert!(!entered.contains("\x1b[?1000h"));
+    }
+
+    #[test]
+    fn test_restore_after_panic() {
+        let guard = std::sync::Arc::new(TerminalGuard::new(noop, noop));
+        guard.enter(&mut Vec::new(), true).unwrap();
+
+        // 模拟按键线程持有锁时 panic，锁中毒不影响还原
+        let lock = std::sync::Arc::new(std::sync::Mutex::new(()));
+        let moved_lock = lock.clone();
+        let moved_guard = guard.clone();
+        let result = std::thread::spawn(move || {
+            let _lock = moved_lock.lock().unwrap();
+            let _ = moved_guard.restore(&mut Vec::new());
+            panic!("key thread panic");
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert!(lock.is_poisoned());
+        let mut out = Vec::new();
+        guard.restore(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_panic_hook() {
+        use std::panic;
+        use std::sync::atomic::AtomicUsize;
+
+        static RESTORED: AtomicUsize = AtomicUsize::new(0);
+        fn count_restore() -> crossterm::Result<()> {
+            RESTORED.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+        static HOOK_GUARD: TerminalGuard = TerminalGuard::new(noop, count_restore);
+
+        HOOK_GUARD.enter(&mut Vec::new(), false).unwrap();
+        // 和 main 里一样在 hook 里还原终端，其他测试线程的 panic 交给原来的 hook
+        let test_thread = std::thread::current().id();
+        let prev = std::sync::Arc::new(panic::take_hook());
+        let moved_prev = prev.clone();
+        panic::set_hook(Box::new(move |info| {
+            if std::thread::current().id() == test_thread {
+                HOOK_GUARD.on_panic(&mut Vec::new(), || {});
+            } else {
+                moved_prev(info);
+            }
+        }));
+        let result = panic::catch_unwind(|| panic!("draw panic"));
+        panic::set_hook(Box::new(move |info| prev(info)));
+
+        assert!(result.is_err());
+        assert_eq!(1, RESTORED.load(Ordering::SeqCst));
+        // 之后正常退出时不会再还原一次
+        HOOK_GUARD.restore(&mut Vec::new()).unwrap();
+        assert_eq!(1, RESTORED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_open_failed() {
+        let guard = TerminalGuard::new(noop, noop);
+        let mut out = Vec::new();
+        let result: io::Result<()> = guard.open(&mut out, true, || Err(io::Error::other("no tty")));
+        assert!(result.is_err());
+        // 创建失败时已经离开备用屏幕，再次还原什么都不做
+        assert!(String::from_utf8_lossy(&out).contains("\x1b[?1049l"));
+        out.clear();
+        guard.restore(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_on_panic() {
+        static PANIC_RAW: AtomicBool = AtomicBool::new(false);
+        fn enable() -> crossterm::Result<()> {
+            PANIC_RAW.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+        fn disable() -> crossterm::Result<()> {
+            PANIC_RAW.store(false, Ordering::SeqCst);
+            Ok(())
+        }
+
+        let guard = TerminalGuard::new(enable, disable);
+        guard.enter(&mut Vec::new(), false).unwrap();
+        let mut reported = false;
+        guard.on_panic(&mut Vec::new(), || {
+            // 打印 panic 信息时终端已经还原
+            assert!(!PANIC_RAW.load(Ordering::SeqCst));
+            reported = true;
+        });
+        assert!(reported);
+    }
+}
@@ -152,7 +152,7 @@ pub fn choose_font_style(category: &Category, theme_style: &ThemeStyle) -> Style
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Default)]
 pub enum Theme {
     /// 浅色默认样式
     LightDefault,
@@ -164,18 +164,13 @@ pub enum Theme {
     DarkDefault,
 
     /// 深色彩色样式
+    #[default]
     DarkColorful,
 
     /// 高对比度
     HighContrast,
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Theme::DarkColorful
-    }
-}
-
 impl Theme {
     pub fn theme_list() -> Vec<&'static str> {
         vec![
@@ -1,80 +1,306 @@
+use tui::style::Style;
 use tui::text::{Span, Spans};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-fn get_total_half_count(origin: String) -> usize {
-    let mut count = 0;
+/// 折行时不可拆开的一段：连续的空格、一个英文单词或者一个中文字
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Space,
+    Word,
+    Wide,
+}
+
+fn kind(grapheme: &str) -> Kind {
+    if grapheme.chars().all(char::is_whitespace) {
+        Kind::Space
+    } else if grapheme.width() >= 2 {
+        Kind::Wide
+    } else {
+        Kind::Word
+    }
+}
+
+/// 按显示宽度折行，英文单词尽量不拆开，中文、emoji 前后都可以断开。
+/// 第一行行首的空格保留，其他行去掉首尾的空格；比一行还长的单词按字拆开。
+/// `width` 小于 2 时放不下一个中文字，这时一个字占一行
+pub fn wrap_spans(line: &Spans, width: usize) -> Vec<Spans<'static>> {
+    let graphemes: Vec<(&str, Style)> = line
+        .0
+        .iter()
+        .flat_map(|span| {
+            span.content
+                .graphemes(true)
+                .map(move |grapheme| (grapheme, span.style))
+        })
+        .collect();
+
+    // 按类型切成一段一段，英文单词和连续的空格合成一段
+    let mut tokens: Vec<&[(&str, Style)]> = vec![];
+    let mut start = 0;
+    for i in 1..=graphemes.len() {
+        let split = i == graphemes.len() || {
+            let (prev, next) = (kind(graphemes[i - 1].0), kind(graphemes[i].0));
+            prev != next || prev == Kind::Wide
+        };
+        if split {
+            tokens.push(&graphemes[start..i]);
+            start = i;
+        }
+    }
 
-    origin.chars().for_each(|c| {
-        if c.is_ascii() {
-            count += 1;
+    let mut wrapper = Wrapper::default();
+    for token in tokens {
+        let token_width: usize = token.iter().map(|(g, _)| g.width()).sum();
+        let is_space = kind(token[0].0) == Kind::Space;
+        if wrapper.used + token_width <= width {
+            // 折出来的行不以空格开头
+            if !is_space || !wrapper.current.is_empty() || wrapper.lines.is_empty() {
+                wrapper.push(token);
+            }
+        } else if is_space {
+            wrapper.flush();
+        } else if token_width > width {
+            for grapheme in token.chunks(1) {
+                if wrapper.used + grapheme[0].0.width() > width && !wrapper.current.is_empty() {
+                    wrapper.flush();
+                }
+                wrapper.push(grapheme);
+            }
         } else {
-            count += 2;
+            wrapper.flush();
+            wrapper.push(token);
         }
-    });
+    }
+    if !wrapper.current.is_empty() || wrapper.lines.is_empty() {
+        wrapper.flush();
+    }
 
-    count
+    wrapper.lines.into_iter().map(merge).collect()
 }
 
-// Splits a string into a vector of strings to appeal to a width (used for word wrap)
-pub fn wrap_lines<'a>(origin: String, line_limit: usize) -> Vec<Spans<'a>> {
-    // Case if "" is passed
-    if origin.is_empty() {
-        return vec![Spans::from(Span::raw(""))];
-    }
+/// 折行的中间状态，`used` 是当前行已经用掉的宽度
+#[derive(Default)]
+struct Wrapper<'a> {
+    lines: Vec<Vec<(&'a str, Style)>>,
+    current: Vec<(&'a str, Style)>,
+    used: usize,
+}
 
-    let total_chars = get_total_half_count(origin.clone());
+impl<'a> Wrapper<'a> {
+    fn push(&mut self, graphemes: &[(&'a str, Style)]) {
+        self.current.extend_from_slice(graphemes);
+        self.used += graphemes.iter().map(|(g, _)| g.width()).sum::<usize>();
+    }
 
-    if total_chars <= line_limit {
-        return vec![Spans::from(Span::raw(origin))];
+    /// 结束当前行，去掉行尾的空格
+    fn flush(&mut self) {
+        while self
+            .current
+            .last()
+            .is_some_and(|(g, _)| kind(g) == Kind::Space)
+        {
+            self.current.pop();
+        }
+        self.lines.push(std::mem::take(&mut self.current));
+        self.used = 0;
     }
+}
 
-    // let mut subs: Vec<Spans> = Vec::with_capacity(origin.len() / line_limit);
-    let mut subs: Vec<Spans> = Vec::new();
+/// 相邻的同样式的字合成一个 Span
+fn merge(graphemes: Vec<(&str, Style)>) -> Spans<'static> {
+    let mut spans: Vec<Span<'static>> = vec![];
+    for (grapheme, style) in graphemes {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(grapheme),
+            _ => spans.push(Span::styled(grapheme.to_string(), style)),
+        }
+    }
+    Spans::from(spans)
+}
 
-    let mut iter = origin.chars();
+/// 按显示宽度折行，保留原来的换行
+pub fn wrap_lines(text: &str, width: usize) -> Vec<Spans<'static>> {
+    text.split('\n')
+        .flat_map(|line| wrap_spans(&Spans::from(line), width))
+        .collect()
+}
 
-    loop {
-        let mut line = String::with_capacity(line_limit);
-        let mut pos = 0;
-        while pos < line_limit {
-            // new line
-            if let Some(c) = iter.next() {
-                if c.is_ascii() {
-                    pos += 1;
-                } else {
-                    pos += 2;
-                }
-                line.push(c);
-            } else {
-                subs.push(Spans::from(Span::raw(line)));
-                return subs;
-            }
+/// 超过显示宽度时截断，末尾用 `…` 表示
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut used = 0;
+    let mut truncated = String::new();
+    for grapheme in text.graphemes(true) {
+        used += grapheme.width();
+        if used + 1 > width {
+            break;
         }
-        subs.push(Spans::from(Span::raw(line)));
+        truncated.push_str(grapheme);
+    }
+    if width > 0 {
+        truncated.truncate(truncated.trim_end().len());
+        truncated.push('…');
     }
+    truncated
 }
 
-pub fn parse_unchecked(content: &str, index: usize) -> usize {
-    let split_result = content.split(' ').into_iter().collect::<Vec<&str>>();
+/// 在右侧补空格到指定的显示宽度，用于在终端输出对齐的表格
+pub fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
 
-    split_result[index].parse::<usize>().unwrap()
+/// 按空格分割后取第 index 个数字，如 `parse_number("第 72 期", 1)`
+pub fn parse_number(content: &str, index: usize) -> Option<usize> {
+    content.split(' ').nth(index)?.parse::<usize>().ok()
+}
+
+/// 本地列表（收藏夹、标签）每页展示的项目数
+pub const LOCAL_PAGE_SIZE: usize = 20;
+
+/// 取出第 page_no 页（从 1 开始），同时返回总页数
+pub fn paginate<T: Clone>(items: &[T], page_no: usize) -> (Vec<T>, usize) {
+    let pages = items.len().div_ceil(LOCAL_PAGE_SIZE);
+    let page = items
+        .iter()
+        .skip(page_no.saturating_sub(1) * LOCAL_PAGE_SIZE)
+        .take(LOCAL_PAGE_SIZE)
+        .cloned()
+        .collect();
+    (page, pages)
+}
+
+/// 每行拼成字符串，测试里比较折行、渲染结果用
+#[cfg(test)]
+pub fn texts(lines: &[Spans]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.0.iter().map(|span| span.content.as_ref()).collect())
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
+    use proptest::prelude::*;
+
     use super::*;
 
     #[test]
     fn test_wrap_lines() {
-        let test_desc = "图解 TLS 连接。用在线交互的方式讲解 TLS 的全过程，从建立 TLS 1.2 客户端发送 ping 再到接收 pong，详细到每一个字节。";
+        let desc = "图解 TLS 连接。用在线交互的方式讲解 TLS 的全过程，从建立 TLS 1.2 客户端发送 ping 再到接收 pong，详细到每一个字节。";
+        let lines = texts(&wrap_lines(desc, 30));
+        assert_eq!(
+            vec![
+                "图解 TLS 连接。用在线交互的方",
+                "式讲解 TLS 的全过程，从建立",
+                "TLS 1.2 客户端发送 ping 再到接",
+                "收 pong，详细到每一个字节。",
+            ],
+            lines
+        );
+
+        // 英文按单词折行
+        assert_eq!(
+            vec!["The uncompromising", "Python code", "formatter"],
+            texts(&wrap_lines("The uncompromising Python code formatter", 18))
+        );
+        // 太长的单词只能拆开
+        assert_eq!(
+            vec!["abcd", "efgh", "ij"],
+            texts(&wrap_lines("abcdefghij", 4))
+        );
+        // 带重音的拉丁字母、组合字符、emoji 的宽度
+        assert_eq!(
+            vec!["café", "nai\u{308}ve"],
+            texts(&wrap_lines("café nai\u{308}ve", 5))
+        );
+        assert_eq!(vec!["🚀🚀", "🚀"], texts(&wrap_lines("🚀🚀🚀", 5)));
+        // 行首的缩进保留，原来的换行保留
+        assert_eq!(
+            vec!["  make", "", "x"],
+            texts(&wrap_lines("  make\n\nx", 10))
+        );
+        assert_eq!(vec![""], texts(&wrap_lines("", 10)));
+    }
+
+    #[test]
+    fn test_wrap_spans_keeps_style() {
+        let red = Style::default().fg(tui::style::Color::Red);
+        let line = Spans::from(vec![Span::raw("hello "), Span::styled("world", red)]);
+        let lines = wrap_spans(&line, 8);
+        assert_eq!(vec!["hello", "world"], texts(&lines));
+        assert_eq!(red, lines[1].0[0].style);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!("ripgrep", truncate("ripgrep", 7));
+        assert_eq!("ripg…", truncate("ripgrep", 5));
+        assert_eq!("rust…", truncate("rust  tui", 6));
+        assert_eq!("命令…", truncate("命令行磁盘空间分析工具", 6));
+        assert_eq!("命…", truncate("命令行磁盘空间分析工具", 4));
+        assert_eq!("…", truncate("命令行", 2));
+        assert_eq!("", truncate("命令行", 0));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_wrap_fits_width(text in "[a-zA-Z0-9 ,.é中文字符测试🚀]{0,120}", width in 2usize..40) {
+            for line in wrap_lines(&text, width) {
+                prop_assert!(line.width() <= width, "{:?} 超过 {}", line, width);
+            }
+        }
+
+        #[test]
+        fn prop_wrap_keeps_text(text in "[a-zA-Z0-9 ,.é中文字符测试🚀]{0,120}", width in 1usize..40) {
+            let wrapped: String = texts(&wrap_lines(&text, width)).concat();
+            let visible = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+            prop_assert_eq!(visible(&text), visible(&wrapped));
+        }
+
+        #[test]
+        fn prop_wrap_keeps_words(text in "[a-z]{1,8}( [a-z]{1,8}){0,20}", width in 8usize..40) {
+            let lines = texts(&wrap_lines(&text, width));
+            let words: Vec<&str> = lines.iter().flat_map(|line| line.split(' ')).collect();
+            prop_assert_eq!(text.split(' ').collect::<Vec<_>>(), words);
+            for line in &lines[1..] {
+                prop_assert!(!line.starts_with(' '));
+            }
+        }
 
-        let lines = wrap_lines(test_desc.to_string(), 30);
-        println!("{:?}", lines);
+        #[test]
+        fn prop_truncate_fits_width(text in "[a-z中文🚀é]{0,40}", width in 0usize..20) {
+            let truncated = truncate(&text, width);
+            prop_assert!(truncated.width() <= width);
+            if text.width() <= width {
+                prop_assert_eq!(text, truncated);
+            }
+        }
     }
 
     #[test]
-    fn test_get_total_half_count() {
-        let test_desc = "图解 TLS 连接。用在线交互的方式讲解 TLS 的全过程，从建立 TLS 1.2 客户端发送 ping 再到接收 pong，详细到每一个字节。";
+    fn test_pad() {
+        assert_eq!("首页  |", format!("{}|", pad("首页", 6)));
+        assert_eq!("abc|", format!("{}|", pad("abc", 2)));
+    }
 
-        println!("{:?}", get_total_half_count(test_desc.to_string()));
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(Some(72), parse_number("第 72 期", 1));
+        assert_eq!(Some(3120), parse_number("3120 个", 0));
+        assert_eq!(None, parse_number("第 期", 1));
+        assert_eq!(None, parse_number("", 1));
+    }
+
+    #[test]
+    fn test_paginate() {
+        let items: Vec<usize> = (0..LOCAL_PAGE_SIZE + 5).collect();
+        assert_eq!(LOCAL_PAGE_SIZE, paginate(&items, 1).0.len());
+        assert_eq!((vec![20, 21, 22, 23, 24], 2), paginate(&items, 2));
+        assert_eq!((vec![], 2), paginate(&items, 3));
+        assert_eq!((vec![], 0), paginate::<usize>(&[], 1));
     }
 }
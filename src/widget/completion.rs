@@ -0,0 +1,231 @@
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app_global::{HG_INFO, THEME};
+use crate::utils;
+use crate::widget::content::Category;
+
+/// 补全弹窗最多展示的行数
+const MAX_HEIGHT: u16 = 8;
+
+/// 输入框下方的补全弹窗
+pub struct Completion {}
+
+/// 一个补全候选，`text` 替换输入框内容，`label` 是说明
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub text: String,
+    pub label: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CompletionState {
+    /// 候选是根据哪个输入算出来的
+    input: String,
+    candidates: Vec<Candidate>,
+    lstate: ListState,
+}
+
+impl CompletionState {
+    /// 输入变化后重新计算候选，输入是当前选中的候选时保留原来的候选，方便继续切换
+    pub fn update(&mut self, input: &str) {
+        let max_volume = HG_INFO.get().map_or(0, |info| info.max_volume);
+        self.update_with(input, max_volume);
+    }
+
+    /// 按给定的最新期数计算候选
+    fn update_with(&mut self, input: &str, max_volume: usize) {
+        let selected = self.lstate.selected().and_then(|i| self.candidates.get(i));
+        if input == self.input || selected.is_some_and(|c| c.text == input) {
+            return;
+        }
+        self.input = input.to_string();
+        self.candidates = candidates(input, max_volume);
+        self.lstate.select(None);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// 切换到下一个（`forward` 为假时上一个）候选，返回要填入输入框的内容
+    pub fn cycle(&mut self, forward: bool) -> Option<&str> {
+        let len = self.candidates.len();
+        if len == 0 {
+            return None;
+        }
+        let selected = match (self.lstate.selected(), forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        self.lstate.select(Some(selected));
+        Some(&self.candidates[selected].text)
+    }
+
+    /// 弹窗需要的高度，包括边框
+    pub fn height(&self) -> u16 {
+        (self.candidates.len() as u16).min(MAX_HEIGHT) + 2
+    }
+}
+
+/// `$` 后补全类别，`#` 后补全期数
+fn candidates(input: &str, max_volume: usize) -> Vec<Candidate> {
+    if let Some(prefix) = input.strip_prefix('$') {
+        category_candidates(prefix)
+    } else if let Some(prefix) = input.strip_prefix(['#', ':']) {
+        volume_candidates(&input[..1], prefix, max_volume)
+    } else {
+        vec![]
+    }
+}
+
+/// 英文名、别名或者中文名以输入开头的类别
+fn category_candidates(prefix: &str) -> Vec<Candidate> {
+    let prefix = prefix.trim().to_lowercase();
+    Category::ALL
+        .into_iter()
+        .filter(|&category| {
+            category
+                .aliases()
+                .iter()
+                .any(|alias| alias.starts_with(&prefix))
+                || String::from(category).to_lowercase().starts_with(&prefix)
+                || category.to_zh().to_lowercase().starts_with(&prefix)
+        })
+        .map(|category| {
+            let aliases = category.aliases();
+            let mut label = category.to_zh();
+            if aliases.len() > 1 {
+                label.push_str(&format!("（{}）", aliases[1..].join(" ")));
+            }
+            Candidate {
+                text: format!("${}", aliases[0]),
+                label,
+            }
+        })
+        .collect()
+}
+
+/// 以输入开头的期数，最新的排在前面，`sign` 是输入的 `#` 或 `:`
+fn volume_candidates(sign: &str, prefix: &str, max_volume: usize) -> Vec<Candidate> {
+    let prefix = prefix.trim();
+    if !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return vec![];
+    }
+    (1..=max_volume)
+        .rev()
+        .filter(|volume| volume.to_string().starts_with(prefix))
+        .map(|volume| Candidate {
+            text: format!("{}{}", sign, volume),
+            label: String::new(),
+        })
+        .collect()
+}
+
+impl StatefulWidget for Completion {
+    type State = CompletionState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let theme_style = THEME.get().unwrap();
+        let width = state
+            .candidates
+            .iter()
+            .map(|c| c.text.width())
+            .max()
+            .unwrap_or(0)
+            + 2;
+
+        let items: Vec<ListItem> = state
+            .candidates
+            .iter()
+            .map(|c| {
+                ListItem::new(Spans::from(vec![
+                    Span::styled(utils::pad(&c.text, width), theme_style.key),
+                    Span::styled(c.label.clone(), theme_style.text),
+                ]))
+            })
+            .collect();
+
+        Clear.render(area, buf);
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Tab 补全 ")
+                    .style(theme_style.background),
+            )
+            .highlight_style(theme_style.selected);
+        StatefulWidget::render(list, area, buf, &mut state.lstate);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn candidate_texts(candidates: Vec<Candidate>) -> Vec<String> {
+        candidates.into_iter().map(|c| c.text).collect()
+    }
+
+    #[test]
+    fn test_category_candidates() {
+        assert_eq!(
+            vec!["$c", "$cpp", "$cs", "$css"],
+            candidate_texts(category_candidates("C"))
+        );
+        assert_eq!(vec!["$objc"], candidate_texts(category_candidates("ob")));
+        assert_eq!(vec!["$ml"], candidate_texts(category_candidates("机器")));
+        assert_eq!(Category::ALL.len(), category_candidates("").len());
+
+        let python = &category_candidates("py")[0];
+        assert_eq!("Python 项目（py）", python.label);
+    }
+
+    #[test]
+    fn test_volume_candidates() {
+        assert_eq!(
+            vec!["#72", "#71", "#70", "#7"],
+            candidate_texts(volume_candidates("#", "7", 72))
+        );
+        assert!(volume_candidates("#", "73", 72).is_empty());
+        assert!(volume_candidates("#", "x", 72).is_empty());
+        assert!(volume_candidates("#", "", 0).is_empty());
+        assert_eq!(
+            vec![":9", ":8"],
+            candidate_texts(volume_candidates(":", "", 9))[..2]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut state = CompletionState::default();
+        state.update_with("python", 72);
+        assert!(state.is_empty());
+
+        state.update_with("#7", 72);
+        assert_eq!(Some("#72"), state.cycle(true));
+        assert_eq!(Some("#7"), state.cycle(false));
+        // 还没有首页信息时没有期数的候选
+        state.update_with("#6", 0);
+        assert!(state.is_empty());
+
+        state.update_with("$c", 72);
+        assert_eq!(Some("$c"), state.cycle(true));
+        assert_eq!(Some("$cpp"), state.cycle(true));
+        // 输入是选中的候选时不重新计算
+        state.update_with("$cpp", 72);
+        assert_eq!(Some("$c"), state.cycle(false));
+        assert_eq!(Some("$css"), state.cycle(false));
+
+        state.update_with("$cs", 72);
+        assert_eq!(
+            vec!["$cs", "$css"],
+            candidate_texts(state.candidates.clone())
+        );
+    }
+}
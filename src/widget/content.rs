@@ -1,18 +1,36 @@
+use std::time::{Duration, Instant};
+
 use anyhow::bail;
+use serde::{Deserialize, Serialize};
 use tui::buffer::Buffer;
 use tui::layout::{Alignment, Constraint, Rect};
-use tui::style::Style;
+use tui::style::{Modifier, Style};
 use tui::text::Span;
 use tui::widgets::{Block, BorderType, Borders, Cell, Row, StatefulWidget, Table, TableState};
 
 use crate::app_global::{HEADERS, THEME};
+use crate::seen::Seen;
 use crate::theme::choose_font_style;
-use crate::utils::parse_unchecked;
+use crate::utils;
 
 const TABLE_TITLE: &str = " 搜索结果 ";
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+/// 每行内容的高度和行间距
+const ROW_HEIGHT: u16 = 1;
+const ROW_MARGIN: u16 = 2;
+
+/// 表头的高度，包括和内容之间的间距
+const HEADER_HEIGHT: u16 = 2;
+
+/// 序号列的宽度，放得下 `•100`
+const NO_WIDTH: u16 = 4;
+
+/// 两次单击间隔不超过这个时间算双击
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default)]
 pub enum Category {
+    #[default]
     Java,
     Python,
     Javascript,
@@ -32,38 +50,18 @@ pub enum Category {
     Other,
 }
 
-impl Default for Category {
-    fn default() -> Self {
-        Self::Java
-    }
-}
-
 impl TryFrom<String> for Category {
     type Error = anyhow::Error;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
         let lower = s.to_lowercase();
-        let category = match lower.as_ref() {
-            "java" => Category::Java,
-            "python" | "py" => Category::Python,
-            "javascript" | "js" => Category::Javascript,
-            "rust" => Category::Rust,
-            "c" => Category::C,
-            "c++" | "cpp" => Category::Cpp,
-            "php" => Category::Php,
-            "objective-c" | "objectc" | "objc" | "oc" => Category::ObjectC,
-            "go" => Category::Go,
-            "css" => Category::Css,
-            "c#" | "cs" => Category::Csharp,
-            "kotlin" => Category::Kotlin,
-            "swift" => Category::Swift,
-            "ml" | "ai" => Category::MachineLearning,
-            "ruby" => Category::Ruby,
-            "book" => Category::Book,
-            "other" => Category::Other,
-            _ => bail!("请输入有效的类别名称，如：java, py, js, go 等"),
-        };
-        Ok(category)
+        match Category::ALL
+            .into_iter()
+            .find(|category| category.aliases().contains(&lower.as_str()))
+        {
+            Some(category) => Ok(category),
+            None => bail!("请输入有效的类别名称，如：java, py, js, go 等"),
+        }
     }
 }
 
@@ -92,6 +90,49 @@ impl From<Category> for String {
 }
 
 impl Category {
+    pub const ALL: [Category; 17] = [
+        Category::Java,
+        Category::Python,
+        Category::Javascript,
+        Category::Rust,
+        Category::C,
+        Category::Cpp,
+        Category::Csharp,
+        Category::ObjectC,
+        Category::Css,
+        Category::Go,
+        Category::Php,
+        Category::Ruby,
+        Category::Swift,
+        Category::Kotlin,
+        Category::MachineLearning,
+        Category::Book,
+        Category::Other,
+    ];
+
+    /// 搜索时可以使用的名称，都是小写，第一个用于补全
+    pub fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Category::Java => &["java"],
+            Category::Python => &["python", "py"],
+            Category::Javascript => &["javascript", "js"],
+            Category::Rust => &["rust"],
+            Category::C => &["c"],
+            Category::Cpp => &["cpp", "c++"],
+            Category::Php => &["php"],
+            Category::ObjectC => &["objc", "objective-c", "objectc", "oc"],
+            Category::Go => &["go"],
+            Category::Css => &["css"],
+            Category::Csharp => &["cs", "c#"],
+            Category::Kotlin => &["kotlin"],
+            Category::Swift => &["swift"],
+            Category::MachineLearning => &["ml", "ai"],
+            Category::Ruby => &["ruby"],
+            Category::Book => &["book"],
+            Category::Other => &["other"],
+        }
+    }
+
     pub fn to_zh(self) -> String {
         match self {
             Category::Java => "Java 项目".into(),
@@ -115,7 +156,7 @@ impl Category {
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Project {
     /// 项目名称
     pub name: String,
@@ -140,13 +181,17 @@ pub struct Project {
 
     /// fork 数
     pub fork: String,
+
+    /// 截图或 logo 的地址，可能是相对地址
+    #[serde(default)]
+    pub image: Option<String>,
 }
 
 impl Project {
     #[allow(clippy::too_many_arguments)]
     pub fn new<T>(
         name: T,
-        volume: T,
+        volume: usize,
         category: T,
         url: T,
         desc: T,
@@ -157,8 +202,6 @@ impl Project {
     where
         T: Into<String>,
     {
-        let volume = parse_unchecked(&volume.into(), 1);
-
         Project {
             name: name.into(),
             volume,
@@ -168,12 +211,21 @@ impl Project {
             star: star.into(),
             watch: watch.into(),
             fork: fork.into(),
+            image: None,
         }
     }
+
+    pub fn with_image(mut self, image: Option<String>) -> Project {
+        self.image = image;
+        self
+    }
 }
 
 /// 数据表格展示
-pub struct Content {}
+pub struct Content<'a> {
+    /// 看过的项目，没看过的行加粗并在序号前加圆点
+    pub seen: &'a Seen,
+}
 
 #[derive(Debug, Default)]
 pub struct ContentState {
@@ -181,7 +233,14 @@ pub struct ContentState {
     cur: Vec<Project>,
     active: bool,
     pub tstate: TableState,
-    pub poor: bool,
+    /// 屏幕上第一行的下标，和 tui 内部的滚动位置一致
+    offset: usize,
+    /// 屏幕上能看到的行数
+    visible: usize,
+    /// 上次绘制的区域，用来判断鼠标点在哪一行
+    area: Rect,
+    /// 上次单击的时间和行，用来判断双击
+    last_click: Option<(Instant, usize)>,
 }
 
 impl ContentState {
@@ -190,6 +249,10 @@ impl ContentState {
         self.cur.append(&mut projects);
     }
 
+    pub fn projects(&self) -> &[Project] {
+        &self.cur
+    }
+
     pub fn active(&mut self) {
         self.active = true;
         if self.tstate.selected().is_none() {
@@ -202,19 +265,18 @@ impl ContentState {
     }
 
     pub fn next(&mut self, incr: usize) {
+        if self.cur.is_empty() {
+            return;
+        }
         let cur = self.tstate.selected().unwrap_or(0);
-        let next = if cur + incr >= self.cur.len() - 1 {
-            self.cur.len() - 1
-        } else {
-            cur + incr
-        };
+        let next = cur.saturating_add(incr).min(self.cur.len() - 1);
         self.tstate.select(Some(next));
     }
 
     pub fn prev(&mut self, incr: usize) {
         let cur = self.tstate.selected().unwrap_or(0);
 
-        let next = if cur < incr { 0 } else { cur - incr };
+        let next = cur.saturating_sub(incr);
         self.tstate.select(Some(next));
     }
 
@@ -222,41 +284,186 @@ impl ContentState {
         self.tstate.select(Some(0));
     }
 
+    /// 选中第 n 行，从 1 开始
+    pub fn goto(&mut self, n: usize) {
+        self.tstate.select(Some(
+            n.saturating_sub(1).min(self.cur.len().saturating_sub(1)),
+        ));
+    }
+
+    /// 选中屏幕上从上往下第 n 行
+    pub fn screen_top(&mut self, n: usize) {
+        let last = self.offset + self.visible.max(1) - 1;
+        self.goto(self.offset.saturating_add(n).min(last + 1));
+    }
+
+    /// 选中屏幕中间一行
+    pub fn screen_middle(&mut self) {
+        self.goto(self.offset + (self.visible.max(1) - 1) / 2 + 1);
+    }
+
+    /// 选中屏幕上从下往上第 n 行
+    pub fn screen_bottom(&mut self, n: usize) {
+        let last = self.offset + self.visible.max(1) - 1;
+        self.goto(last.saturating_sub(n - 1).max(self.offset) + 1);
+    }
+
+    /// 半屏的行数，Ctrl-d/Ctrl-u 移动的距离
+    pub fn half_screen(&self) -> usize {
+        (self.visible / 2).max(1)
+    }
+
+    /// 鼠标所在的行，不在表格的数据行上时返回 None
+    pub fn row_at(&self, x: u16, y: u16) -> Option<usize> {
+        let top = self.area.y + 1 + HEADER_HEIGHT;
+        if x <= self.area.x || x + 1 >= self.area.right() || y < top {
+            return None;
+        }
+        // 行下方的空白也算这一行
+        let row = self.offset + ((y - top) / (ROW_HEIGHT + ROW_MARGIN)) as usize;
+        (row < self.offset + self.visible && row < self.cur.len()).then_some(row)
+    }
+
+    /// 鼠标是否在表格上
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.area.x && x < self.area.right() && y >= self.area.y && y < self.area.bottom()
+    }
+
+    /// 单击选中一行，返回是否是双击
+    pub fn click(&mut self, row: usize, now: Instant) -> bool {
+        self.tstate.select(Some(row));
+        let double = matches!(
+            self.last_click,
+            Some((time, last)) if last == row && now.duration_since(time) <= DOUBLE_CLICK
+        );
+        // 双击之后重新计时，连点三下不算两次双击
+        self.last_click = if double { None } else { Some((now, row)) };
+        double
+    }
+
     pub fn last(&mut self) {
-        self.tstate.select(Some(self.cur.len() - 1));
+        self.tstate.select(Some(self.cur.len().saturating_sub(1)));
+    }
+
+    /// 删除数据后选中行可能超出范围，移到最后一行
+    pub fn clamp_selected(&mut self) {
+        if self.tstate.selected().unwrap_or(0) >= self.cur.len() {
+            self.last();
+        }
+    }
+
+    pub fn get_selected(&self) -> Option<Project> {
+        self.cur.get(self.tstate.selected()?).cloned()
+    }
+}
+
+/// 和 tui 的 `Table` 一样计算屏幕上能看到的行 `[start, end)`。
+/// tui 没有公开滚动位置，H/M/L 这些按屏幕移动的操作只能自己再算一遍
+fn row_bounds(
+    len: usize,
+    selected: Option<usize>,
+    offset: usize,
+    max_height: u16,
+) -> (usize, usize) {
+    let total = ROW_HEIGHT + ROW_MARGIN;
+    let offset = offset.min(len.saturating_sub(1));
+    let (mut start, mut end, mut height) = (offset, offset, 0u16);
+    for _ in offset..len {
+        if height + ROW_HEIGHT > max_height {
+            break;
+        }
+        height += total;
+        end += 1;
     }
 
-    pub fn get_selected(&self) -> Project {
-        self.cur
-            .get(self.tstate.selected().unwrap())
-            .unwrap()
-            .clone()
+    let selected = selected.unwrap_or(0).min(len - 1);
+    while selected >= end {
+        height = height.saturating_add(total);
+        end += 1;
+        while height > max_height {
+            height = height.saturating_sub(total);
+            start += 1;
+        }
+    }
+    while selected < start {
+        start -= 1;
+        height = height.saturating_add(total);
+        while height > max_height {
+            end -= 1;
+            height = height.saturating_sub(total);
+        }
     }
+    (start, end)
+}
+
+/// 各列的宽度，和 `HEADERS` 一一对应。序号列固定宽度，其他列按比例分掉剩下的宽度
+fn column_widths(width: u16) -> [u16; 5] {
+    // 去掉边框和列之间的空格
+    // 超过 4369 列时 `rest * 15` 会超出 u16，按 u32 算
+    let rest = width.saturating_sub(2 + 4 + NO_WIDTH) as u32;
+    let name = (rest * 15 / 97) as u16;
+    let volume = (rest * 10 / 97) as u16;
+    let category = (rest * 10 / 97) as u16;
+    [
+        NO_WIDTH,
+        name,
+        volume,
+        category,
+        rest as u16 - name - volume - category,
+    ]
 }
 
 fn new_cell<'a>(symbol: impl ToString, style: Style) -> Cell<'a> {
     Cell::from(symbol.to_string()).style(style)
 }
 
-impl StatefulWidget for Content {
+impl<'a> StatefulWidget for Content<'a> {
     type State = ContentState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let theme_style = THEME.get().unwrap();
 
+        state.area = area;
+        if !state.cur.is_empty() {
+            // 去掉边框和表头
+            let max_height = area.height.saturating_sub(2 + HEADER_HEIGHT);
+            let (start, end) = row_bounds(
+                state.cur.len(),
+                state.tstate.selected(),
+                state.offset,
+                max_height,
+            );
+            state.offset = start;
+            state.visible = end - start;
+        }
+
         let header_cells = HEADERS
             .iter()
             .map(|h| Cell::from(*h).style(theme_style.title));
         let header = Row::new(header_cells)
             // .style(normal_style)
             .height(1)
-            .bottom_margin(1);
-
+            .bottom_margin(HEADER_HEIGHT - 1);
+
+        let widths = column_widths(area.width);
+        // 放不下的内容用省略号截断
+        let cell = |text: &str, column: usize| {
+            new_cell(
+                utils::truncate(text, widths[column] as usize),
+                Style::default(),
+            )
+        };
         let rows = state.cur.iter().enumerate().map(|(i, project)| {
             let mut cells: Vec<Cell> = Vec::with_capacity(5);
 
-            cells.push(new_cell(i + 1, Style::default()));
-            cells.push(new_cell(project.name.clone(), Style::default()));
+            let seen = self.seen.is_seen(&project.url);
+            let no = if seen {
+                format!(" {}", i + 1)
+            } else {
+                format!("•{}", i + 1)
+            };
+            cells.push(new_cell(no, Style::default()));
+            cells.push(cell(&project.name, 1));
             cells.push(new_cell(project.volume, Style::default()));
 
             let category = if let Ok(category) = Category::try_from(project.category.clone()) {
@@ -267,15 +474,23 @@ impl StatefulWidget for Content {
 
             let color_style = choose_font_style(&category, theme_style);
 
-            cells.push(new_cell(project.category.clone(), Style::default()));
-            cells.push(new_cell(project.desc.clone(), Style::default()));
+            cells.push(cell(&project.category, 3));
+            cells.push(cell(&project.desc, 4));
 
             let style = match state.tstate.selected() {
                 Some(index) if index == i => theme_style.selected,
                 _ => color_style,
             };
+            let style = if seen {
+                style
+            } else {
+                style.add_modifier(Modifier::BOLD)
+            };
 
-            Row::new(cells).height(1).bottom_margin(2).style(style)
+            Row::new(cells)
+                .height(ROW_HEIGHT)
+                .bottom_margin(ROW_MARGIN)
+                .style(style)
         });
 
         let table_title = if state.active {
@@ -290,13 +505,127 @@ impl StatefulWidget for Content {
             .title_alignment(Alignment::Center)
             .title(table_title);
 
-        let t = Table::new(rows).header(header).block(table_block).widths(&[
-            Constraint::Percentage(3),
-            Constraint::Percentage(15),
-            Constraint::Percentage(10),
-            Constraint::Percentage(10),
-            Constraint::Percentage(62),
-        ]);
+        let constraints = widths.map(Constraint::Length);
+        let t = Table::new(rows)
+            .header(header)
+            .block(table_block)
+            .widths(&constraints);
         <Table as StatefulWidget>::render(t, area, buf, &mut state.tstate)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn state(len: usize) -> ContentState {
+        let mut state = ContentState::default();
+        let projects = (0..len)
+            .map(|i| Project::new(i.to_string().as_str(), 72, "C", "", "", "", "", ""))
+            .collect();
+        state.add_projects(projects);
+        state.first();
+        state
+    }
+
+    /// tui 实际画出来的第一行
+    fn rendered_start(len: usize, tstate: &mut TableState, area: Rect) -> usize {
+        let rows = (0..len).map(|i| {
+            Row::new(vec![Cell::from(i.to_string())])
+                .height(ROW_HEIGHT)
+                .bottom_margin(ROW_MARGIN)
+        });
+        let table = Table::new(rows)
+            .header(Row::new(vec!["№"]).bottom_margin(HEADER_HEIGHT - 1))
+            .block(Block::default().borders(Borders::ALL))
+            .widths(&[Constraint::Percentage(100)]);
+        let mut buf = Buffer::empty(area);
+        StatefulWidget::render(table, area, &mut buf, tstate);
+        let line: String = (1..area.width - 1)
+            .map(|x| buf.get(x, 1 + HEADER_HEIGHT).symbol.as_str())
+            .collect();
+        line.trim().parse().unwrap()
+    }
+
+    #[test]
+    fn test_column_widths() {
+        for width in [20, 68, 140, 300, 5000, u16::MAX] {
+            let widths = column_widths(width);
+            // 加上边框和列之间的空格正好占满
+            assert_eq!(width, widths.iter().sum::<u16>() + 6);
+            assert_eq!(NO_WIDTH, widths[0]);
+        }
+        assert_eq!([4, 19, 13, 13, 83], column_widths(138));
+        assert_eq!([4, 0, 0, 0, 0], column_widths(5));
+    }
+
+    #[test]
+    fn test_row_bounds() {
+        let (len, area) = (30, Rect::new(0, 0, 10, 20));
+        let max_height = area.height - 2 - HEADER_HEIGHT;
+        let mut tstate = TableState::default();
+        let mut offset = 0;
+        for selected in [0, 3, 4, 9, 29, 20, 15, 14, 2, 0, 25] {
+            tstate.select(Some(selected));
+            let start = rendered_start(len, &mut tstate, area);
+            let (our_start, end) = row_bounds(len, Some(selected), offset, max_height);
+            assert_eq!(start, our_start, "selected {}", selected);
+            assert!((our_start..end).contains(&selected));
+            offset = our_start;
+        }
+    }
+
+    #[test]
+    fn test_screen_moves() {
+        let mut state = state(30);
+        // 模拟画过一次，屏幕上是第 10 到第 14 行
+        state.offset = 9;
+        state.visible = 5;
+
+        state.screen_top(1);
+        assert_eq!(Some(9), state.tstate.selected());
+        state.screen_top(3);
+        assert_eq!(Some(11), state.tstate.selected());
+        state.screen_middle();
+        assert_eq!(Some(11), state.tstate.selected());
+        state.screen_bottom(1);
+        assert_eq!(Some(13), state.tstate.selected());
+        state.screen_bottom(10);
+        assert_eq!(Some(9), state.tstate.selected());
+        assert_eq!(2, state.half_screen());
+
+        state.goto(100);
+        assert_eq!(Some(29), state.tstate.selected());
+        state.goto(0);
+        assert_eq!(Some(0), state.tstate.selected());
+    }
+
+    #[test]
+    fn test_mouse() {
+        let mut state = state(30);
+        // 表格从第 5 行开始，表头和边框占 3 行，数据从第 8 行开始，每行占 3 行
+        state.area = Rect::new(2, 5, 80, 20);
+        state.offset = 9;
+        state.visible = 5;
+
+        assert_eq!(None, state.row_at(10, 7));
+        assert_eq!(Some(9), state.row_at(10, 8));
+        assert_eq!(Some(9), state.row_at(10, 10));
+        assert_eq!(Some(10), state.row_at(10, 11));
+        assert_eq!(Some(13), state.row_at(10, 20));
+        assert_eq!(None, state.row_at(10, 23));
+        // 边框上
+        assert_eq!(None, state.row_at(2, 8));
+        assert_eq!(None, state.row_at(81, 8));
+        assert!(state.contains(2, 5));
+        assert!(!state.contains(82, 5));
+
+        let now = Instant::now();
+        assert!(!state.click(10, now));
+        assert_eq!(Some(10), state.tstate.selected());
+        assert!(state.click(10, now + Duration::from_millis(200)));
+        assert!(!state.click(10, now + Duration::from_millis(300)));
+        assert!(!state.click(11, now + Duration::from_millis(400)));
+        assert!(!state.click(11, now + Duration::from_secs(1)));
+    }
+}
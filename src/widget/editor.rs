@@ -0,0 +1,344 @@
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::Style;
+use tui::widgets::{Block, Borders, Paragraph, StatefulWidget, Widget};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::app_global::THEME;
+
+/// 文本编辑框，支持多行
+pub struct Editor<'a> {
+    pub title: &'a str,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditorState {
+    lines: Vec<String>,
+    /// 光标所在行
+    row: usize,
+    /// 光标所在列，按字符计
+    col: usize,
+    /// 是否允许换行
+    multiline: bool,
+    /// 滚动的行数，保证光标可见
+    scroll: usize,
+    /// 横向滚动的列数，光标所在行超出编辑框时保证光标可见
+    hscroll: u16,
+    active: bool,
+}
+
+impl Default for EditorState {
+    fn default() -> EditorState {
+        EditorState::new("", true)
+    }
+}
+
+impl EditorState {
+    pub fn new(text: &str, multiline: bool) -> EditorState {
+        let mut lines: Vec<String> = text.lines().map(String::from).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        let row = lines.len() - 1;
+        let col = lines[row].chars().count();
+        EditorState {
+            lines,
+            row,
+            col,
+            multiline,
+            scroll: 0,
+            hscroll: 0,
+            active: false,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// 光标所在行的内容，单行输入框用它代替 `text`
+    pub fn line(&self) -> &str {
+        &self.lines[self.row]
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let idx = self.byte_index();
+        self.lines[self.row].insert(idx, c);
+        self.col += 1;
+    }
+
+    pub fn newline(&mut self) {
+        if !self.multiline {
+            return;
+        }
+        let idx = self.byte_index();
+        let rest = self.lines[self.row].split_off(idx);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let idx = self.byte_index();
+            self.lines[self.row].remove(idx);
+        } else if self.row > 0 {
+            // 行首退格，和上一行合并
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let idx = self.byte_index();
+            self.lines[self.row].remove(idx);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line_len();
+    }
+
+    /// 移动到上一个词的开头，词以空白分隔
+    pub fn word_left(&mut self) {
+        self.col = self.word_start();
+    }
+
+    /// 移动到下一个词的末尾
+    pub fn word_right(&mut self) {
+        let chars: Vec<char> = self.lines[self.row].chars().collect();
+        let mut col = self.col;
+        while col < chars.len() && chars[col].is_whitespace() {
+            col += 1;
+        }
+        while col < chars.len() && !chars[col].is_whitespace() {
+            col += 1;
+        }
+        self.col = col;
+    }
+
+    /// 删除光标前的一个词，和 shell 的 Ctrl-W 一样以空白分隔
+    pub fn delete_word(&mut self) {
+        self.remove(self.word_start(), self.col);
+    }
+
+    /// 删除光标前到行首的内容
+    pub fn delete_to_start(&mut self) {
+        self.remove(0, self.col);
+    }
+
+    /// 删除光标后到行尾的内容
+    pub fn delete_to_end(&mut self) {
+        self.remove(self.col, self.line_len());
+    }
+
+    /// 光标相对于编辑区左上角的位置，按显示宽度计算，中文占两列
+    pub fn cursor(&self) -> (u16, u16) {
+        (
+            self.col_width().saturating_sub(self.hscroll),
+            self.row.saturating_sub(self.scroll) as u16,
+        )
+    }
+
+    /// 光标前内容的显示宽度，不考虑横向滚动
+    pub fn col_width(&self) -> u16 {
+        let before: String = self.lines[self.row].chars().take(self.col).collect();
+        before.width() as u16
+    }
+
+    /// 根据编辑区的大小滚动，保证光标在编辑区内
+    fn scroll_to_cursor(&mut self, width: u16, height: usize) {
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+
+        // 光标在行尾时也要占一列
+        let col = self.col_width();
+        if col < self.hscroll {
+            self.hscroll = col;
+        } else if col >= self.hscroll + width {
+            self.hscroll = col + 1 - width;
+        }
+    }
+
+    /// 光标所在行光标前一个词的开头
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.lines[self.row].chars().take(self.col).collect();
+        let mut start = chars.len();
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        start
+    }
+
+    /// 删除光标所在行按字符计的 `start..end`，光标移到删除的位置
+    fn remove(&mut self, start: usize, end: usize) {
+        let line = &mut self.lines[self.row];
+        let byte = |col: usize| line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+        let range = byte(start)..byte(end);
+        line.replace_range(range, "");
+        self.col = start;
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(idx, _)| idx)
+    }
+}
+
+impl<'a> StatefulWidget for Editor<'a> {
+    type State = EditorState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let theme_style = THEME.get().unwrap();
+        let style = if state.active {
+            theme_style.title
+        } else {
+            Style::default()
+        };
+
+        let width = area.width.saturating_sub(2).max(1);
+        let height = area.height.saturating_sub(2).max(1) as usize;
+        state.scroll_to_cursor(width, height);
+
+        Paragraph::new(state.text())
+            .scroll((state.scroll as u16, state.hscroll))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title)
+                    .style(style),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn typing(state: &mut EditorState, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                state.newline();
+            } else {
+                state.insert(c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut state = EditorState::new("", true);
+        typing(&mut state, "许可证\nAGPL");
+        assert_eq!("许可证\nAGPL", state.text());
+        assert_eq!((4, 1), state.cursor());
+
+        // 回到上一行末尾插入
+        state.up();
+        state.end();
+        assert_eq!((6, 0), state.cursor());
+        typing(&mut state, "：");
+        assert_eq!("许可证：\nAGPL", state.text());
+
+        // 行首退格合并两行
+        state.down();
+        state.home();
+        state.backspace();
+        assert_eq!("许可证：AGPL", state.text());
+        assert_eq!((8, 0), state.cursor());
+
+        state.left();
+        state.delete();
+        assert_eq!("许可证AGPL", state.text());
+    }
+
+    #[test]
+    fn test_single_line() {
+        let mut state = EditorState::new("cli", false);
+        typing(&mut state, "\n disk");
+        assert_eq!("cli disk", state.text());
+        state.home();
+        state.right();
+        state.insert('!');
+        assert_eq!("c!li disk", state.text());
+    }
+
+    #[test]
+    fn test_scroll_to_cursor() {
+        let mut state = EditorState::new("", false);
+        typing(&mut state, "rust 命令行工具");
+        assert_eq!((15, 0), state.cursor());
+
+        // 编辑区只有 10 列，光标停在最右边一列
+        state.scroll_to_cursor(10, 1);
+        assert_eq!((9, 0), state.cursor());
+        state.home();
+        state.scroll_to_cursor(10, 1);
+        assert_eq!((0, 0), state.cursor());
+        assert_eq!(0, state.hscroll);
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use tui::{buffer::Buffer, layout::Rect, style::Color, widgets::StatefulWidget};
+
+use crate::screenshot;
+
+/// 用半格字符画图片，一个字符上下两个像素，`▀` 的前景色是上面的像素，背景色是下面的像素
+pub struct HalfBlock<'a> {
+    pub image: &'a Arc<DynamicImage>,
+}
+
+/// 缩放后的图片，每秒一次的时钟也会重绘，图片和位置都没变时不重新缩放
+#[derive(Debug, Default)]
+pub struct HalfBlockState {
+    resized: Option<(Arc<DynamicImage>, Rect, RgbaImage)>,
+}
+
+impl<'a> StatefulWidget for HalfBlock<'a> {
+    type State = HalfBlockState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        if area.area() == 0 {
+            return;
+        }
+        // 一个字符当作宽 1 高 2 的两个像素
+        let rect = screenshot::fit(self.image.dimensions(), area, (1, 2));
+        let pixels = match &state.resized {
+            Some((image, resized_rect, pixels))
+                if Arc::ptr_eq(image, self.image) && *resized_rect == rect =>
+            {
+                pixels
+            }
+            _ => {
+                let pixels = self
+                    .image
+                    .thumbnail_exact(rect.width as u32, rect.height as u32 * 2)
+                    .to_rgba8();
+                &state.resized.insert((self.image.clone(), rect, pixels)).2
+            }
+        };
+
+        for y in 0..rect.height {
+            for x in 0..rect.width {
+                let top = color(pixels.get_pixel(x as u32, y as u32 * 2));
+                let bottom = color(pixels.get_pixel(x as u32, y as u32 * 2 + 1));
+                let cell = buf.get_mut(rect.x + x, rect.y + y);
+                match (top, bottom) {
+                    (Some(top), Some(bottom)) => {
+                        cell.set_symbol("▀").set_fg(top).set_bg(bottom);
+                    }
+                    (Some(top), None) => {
+                        cell.set_symbol("▀").set_fg(top);
+                    }
+                    (None, Some(bottom)) => {
+                        cell.set_symbol("▄").set_fg(bottom);
+                    }
+                    // 透明的地方保留原来的背景
+                    (None, None) => {}
+                }
+            }
+        }
+    }
+}
+
+fn color(pixel: &Rgba<u8>) -> Option<Color> {
+    (pixel[3] >= 128).then(|| Color::Rgb(pixel[0], pixel[1], pixel[2]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        // 2x2：上面一行红色，左下透明，右下蓝色
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(1, 1, Rgba([0, 0, 255, 255]));
+        let image = Arc::new(DynamicImage::ImageRgba8(image));
+
+        let area = Rect::new(0, 0, 2, 1);
+        let mut buf = Buffer::empty(area);
+        let mut state = HalfBlockState::default();
+        HalfBlock { image: &image }.render(area, &mut buf, &mut state);
+
+        let left = buf.get(0, 0);
+        assert_eq!("▀", left.symbol);
+        assert_eq!(Color::Rgb(255, 0, 0), left.fg);
+        assert_eq!(Color::Reset, left.bg);
+
+        let right = buf.get(1, 0);
+        assert_eq!("▀", right.symbol);
+        assert_eq!(Color::Rgb(255, 0, 0), right.fg);
+        assert_eq!(Color::Rgb(0, 0, 255), right.bg);
+
+        // 再画一遍用缓存的图片，换了位置才重新缩放
+        let (_, _, cached) = state.resized.as_ref().unwrap();
+        let cached = cached.as_ptr();
+        HalfBlock { image: &image }.render(area, &mut buf, &mut state);
+        assert_eq!(cached, state.resized.as_ref().unwrap().2.as_ptr());
+        let moved = Rect::new(0, 1, 2, 1);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 2, 2));
+        HalfBlock { image: &image }.render(moved, &mut buf, &mut state);
+        assert_eq!(moved, state.resized.as_ref().unwrap().1);
+        assert_eq!(Color::Rgb(0, 0, 255), buf.get(1, 1).bg);
+    }
+}
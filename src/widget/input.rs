@@ -8,23 +8,45 @@ use unicode_width::UnicodeWidthStr;
 
 use crate::app::SearchMode;
 use crate::app_global::THEME;
+use crate::history::History;
+use crate::widget::editor::EditorState;
 
 /// 用户输入框组件
 pub struct Input {}
 
 #[derive(Debug)]
 pub struct InputState {
-    input: String,
+    /// 单行的编辑框，负责光标移动和增删
+    editor: EditorState,
+    /// 输入内容超出输入框时横向滚动的列数
+    offset: u16,
     active: bool,
     pub mode: SearchMode,
+    /// 正在查看的搜索记录下标，`None` 表示在编辑新的输入
+    history_pos: Option<usize>,
+    /// 开始翻看搜索记录前的输入和搜索模式，翻到底时还原
+    draft: (String, SearchMode),
+    /// Ctrl-R 反向搜索的状态
+    reverse: Option<ReverseSearch>,
+}
+
+/// 反向搜索，输入的内容和找到的记录下标
+#[derive(Debug, Default)]
+struct ReverseSearch {
+    pattern: String,
+    found: Option<usize>,
 }
 
 impl Default for InputState {
     fn default() -> InputState {
         InputState {
-            input: String::default(),
+            editor: EditorState::new("", false),
+            offset: 0,
             active: true,
             mode: SearchMode::Normal,
+            history_pos: None,
+            draft: (String::new(), SearchMode::Normal),
+            reverse: None,
         }
     }
 }
@@ -38,35 +60,227 @@ impl InputState {
         self.active = false;
     }
 
-    pub fn width(&self) -> u16 {
-        self.input.width() as u16
+    /// 光标相对于输入框内容左侧的位置，按显示宽度计算，中文占两列
+    pub fn cursor(&self) -> u16 {
+        self.cursor_width().saturating_sub(self.offset)
+    }
+
+    /// 光标前内容的显示宽度，不考虑横向滚动
+    fn cursor_width(&self) -> u16 {
+        match &self.reverse {
+            Some(reverse) => format!("(搜索历史)`{}", reverse.pattern).width() as u16,
+            None => self.editor.col_width(),
+        }
+    }
+
+    /// 输入框里展示的内容，反向搜索时展示搜索词和找到的记录
+    fn display(&self) -> String {
+        match &self.reverse {
+            Some(reverse) => format!("(搜索历史)`{}`：{}", reverse.pattern, self.text()),
+            None => self.text().to_string(),
+        }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.input.is_empty()
+        self.text().is_empty()
+    }
+
+    pub fn text(&self) -> &str {
+        self.editor.line()
+    }
+
+    /// 替换输入内容，补全时使用
+    pub fn replace(&mut self, text: &str) -> SearchMode {
+        self.set_input(text);
+        self.changed()
     }
 
     pub fn clear(&mut self) -> String {
-        let content = self.input.clone();
-        self.input.clear();
+        let content = self.text().to_string();
+        self.editor = EditorState::new("", false);
+        self.history_pos = None;
         content
     }
 
+    /// 在光标处插入字符
     pub fn handle_char(&mut self, char: char) -> SearchMode {
-        if self.input.is_empty() {
-            // 说明当前 char 是第一个字符
-            self.mode = match char {
-                '#' => SearchMode::Volume,
-                '$' => SearchMode::Category,
-                _ => SearchMode::Normal,
+        self.editor.insert(char);
+        self.changed()
+    }
+
+    /// 删除光标前的字符
+    pub fn handle_backspace(&mut self) -> SearchMode {
+        self.editor.backspace();
+        self.changed()
+    }
+
+    /// 删除光标处的字符
+    pub fn delete(&mut self) -> SearchMode {
+        self.editor.delete();
+        self.changed()
+    }
+
+    /// 删除光标前的一个词，和 shell 的 Ctrl-W 一样以空白分隔
+    pub fn delete_word(&mut self) -> SearchMode {
+        self.editor.delete_word();
+        self.changed()
+    }
+
+    /// 删除光标前的所有内容
+    pub fn delete_to_start(&mut self) -> SearchMode {
+        self.editor.delete_to_start();
+        self.changed()
+    }
+
+    /// 删除光标后的所有内容
+    pub fn delete_to_end(&mut self) -> SearchMode {
+        self.editor.delete_to_end();
+        self.changed()
+    }
+
+    pub fn left(&mut self) {
+        self.editor.left();
+    }
+
+    pub fn right(&mut self) {
+        self.editor.right();
+    }
+
+    pub fn home(&mut self) {
+        self.editor.home();
+    }
+
+    pub fn end(&mut self) {
+        self.editor.end();
+    }
+
+    /// 移动到上一个词的开头
+    pub fn word_left(&mut self) {
+        self.editor.word_left();
+    }
+
+    /// 移动到下一个词的末尾
+    pub fn word_right(&mut self) {
+        self.editor.word_right();
+    }
+
+    /// 内容变化后不再处于翻看记录的状态，搜索模式跟着第一个字符变化
+    fn changed(&mut self) -> SearchMode {
+        self.history_pos = None;
+        if let Some(char) = self.text().chars().next() {
+            self.mode = mode_of(char);
+        }
+        self.mode
+    }
+
+    /// 替换输入内容，光标移到末尾，搜索模式跟着第一个字符变化
+    fn set_input(&mut self, input: &str) {
+        self.editor = EditorState::new(input, false);
+        self.mode = input.chars().next().map_or(SearchMode::Normal, mode_of);
+    }
+
+    /// 还原翻看记录前的输入，输入为空时模式也不会变成普通搜索
+    fn restore_draft(&mut self) {
+        let (draft, mode) = std::mem::replace(&mut self.draft, (String::new(), SearchMode::Normal));
+        self.set_input(&draft);
+        self.mode = mode;
+    }
+
+    /// 上一条搜索记录
+    pub fn prev_history(&mut self, history: &History) -> SearchMode {
+        let pos = match self.history_pos {
+            _ if history.len() == 0 => return self.mode,
+            None => {
+                self.draft = (self.text().to_string(), self.mode);
+                history.len() - 1
+            }
+            Some(pos) => pos.saturating_sub(1),
+        };
+        self.history_pos = Some(pos);
+        self.set_input(history.get(pos).unwrap_or_default());
+        self.mode
+    }
+
+    /// 下一条搜索记录，翻到底后还原开始翻看前的输入
+    pub fn next_history(&mut self, history: &History) -> SearchMode {
+        match self.history_pos {
+            None => {}
+            Some(pos) if pos + 1 < history.len() => {
+                self.history_pos = Some(pos + 1);
+                self.set_input(history.get(pos + 1).unwrap_or_default());
+            }
+            Some(_) => {
+                self.history_pos = None;
+                self.restore_draft();
             }
         }
-        self.input.push(char);
         self.mode
     }
 
-    pub fn handle_backspace(&mut self) {
-        self.input.pop();
+    pub fn is_reverse_searching(&self) -> bool {
+        self.reverse.is_some()
+    }
+
+    /// 开始反向搜索，已经在搜索时找更早的一条
+    pub fn reverse_search(&mut self, history: &History) -> SearchMode {
+        match &mut self.reverse {
+            None => {
+                self.draft = (self.text().to_string(), self.mode);
+                self.reverse = Some(ReverseSearch::default());
+            }
+            Some(reverse) => {
+                let before = reverse.found.unwrap_or(history.len());
+                if let Some(found) = history.rfind(&reverse.pattern, before) {
+                    reverse.found = Some(found);
+                    self.set_input(history.get(found).unwrap_or_default());
+                }
+            }
+        }
+        self.mode
+    }
+
+    /// 反向搜索时输入或删除字符，从当前找到的记录开始往前找
+    pub fn reverse_input(&mut self, history: &History, char: Option<char>) -> SearchMode {
+        let reverse = match &mut self.reverse {
+            Some(reverse) => reverse,
+            None => return self.mode,
+        };
+        let before = match char {
+            Some(char) => {
+                reverse.pattern.push(char);
+                reverse.found.map_or(history.len(), |found| found + 1)
+            }
+            None => {
+                reverse.pattern.pop();
+                history.len()
+            }
+        };
+        reverse.found = history.rfind(&reverse.pattern, before);
+        let input = match reverse.found {
+            Some(found) => history.get(found).unwrap_or_default().to_string(),
+            None => String::new(),
+        };
+        self.set_input(&input);
+        self.mode
+    }
+
+    /// 结束反向搜索，`accept` 为假时还原搜索前的输入
+    pub fn finish_reverse(&mut self, accept: bool) -> SearchMode {
+        if self.reverse.take().is_some() && !accept {
+            self.restore_draft();
+        }
+        self.mode
+    }
+}
+
+/// 第一个字符决定搜索模式
+fn mode_of(char: char) -> SearchMode {
+    match char {
+        '#' | ':' => SearchMode::Volume,
+        '$' => SearchMode::Category,
+        '@' => SearchMode::Favorite,
+        '%' => SearchMode::Tag,
+        _ => SearchMode::Normal,
     }
 }
 
@@ -81,8 +295,146 @@ impl StatefulWidget for Input {
             Style::default()
         };
 
-        Paragraph::new(state.input.as_ref())
+        // 横向滚动，保证光标可见
+        let width = area.width.saturating_sub(3);
+        let cursor = state.cursor_width();
+        if cursor < state.offset {
+            state.offset = cursor;
+        } else if cursor > state.offset + width {
+            state.offset = cursor - width;
+        }
+
+        Paragraph::new(state.display())
+            .scroll((0, state.offset))
             .block(Block::default().borders(Borders::ALL).style(style))
             .render(area, buf);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn history() -> History {
+        let mut history = History::default();
+        for query in ["python", "#72", "$rust", "python web"] {
+            history.push(query);
+        }
+        history
+    }
+
+    fn typing(state: &mut InputState, text: &str) {
+        for c in text.chars() {
+            state.handle_char(c);
+        }
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut state = InputState::default();
+        typing(&mut state, "rust 命令行");
+        assert_eq!(11, state.cursor());
+
+        // 中文占两列
+        state.left();
+        assert_eq!(9, state.cursor());
+        state.handle_backspace();
+        assert_eq!("rust 命行", state.text());
+
+        state.home();
+        assert_eq!(SearchMode::Category, state.handle_char('$'));
+        state.delete();
+        assert_eq!("$ust 命行", state.text());
+        state.end();
+        state.delete_to_start();
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_word() {
+        let mut state = InputState::default();
+        typing(&mut state, "python  web 框架");
+
+        state.word_left();
+        assert_eq!(12, state.cursor());
+        state.word_left();
+        state.word_left();
+        assert_eq!(0, state.cursor());
+        state.word_right();
+        assert_eq!(6, state.cursor());
+        state.word_right();
+        assert_eq!(11, state.cursor());
+
+        state.delete_to_end();
+        assert_eq!("python  web", state.text());
+        state.delete_word();
+        assert_eq!("python  ", state.text());
+        state.delete_word();
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_history() {
+        let history = history();
+        let mut state = InputState::default();
+        typing(&mut state, "go");
+
+        state.prev_history(&history);
+        assert_eq!("python web", state.text());
+        assert_eq!(SearchMode::Category, state.prev_history(&history));
+        state.prev_history(&history);
+        state.prev_history(&history);
+        // 到第一条后停住
+        state.prev_history(&history);
+        assert_eq!("python", state.text());
+
+        state.next_history(&history);
+        assert_eq!(SearchMode::Volume, state.mode);
+        state.next_history(&history);
+        state.next_history(&history);
+        // 翻到底还原之前的输入
+        assert_eq!(SearchMode::Normal, state.next_history(&history));
+        assert_eq!("go", state.text());
+
+        // 搜完类别后输入框清空，翻看记录再回来还是类别模式
+        state.replace("$rust");
+        state.clear();
+        state.prev_history(&history);
+        assert_eq!(SearchMode::Category, state.next_history(&history));
+        assert_eq!("", state.text());
+    }
+
+    #[test]
+    fn test_reverse_search() {
+        let history = history();
+        let mut state = InputState::default();
+        typing(&mut state, "go");
+
+        state.reverse_search(&history);
+        state.reverse_input(&history, Some('p'));
+        state.reverse_input(&history, Some('y'));
+        assert_eq!("python web", state.text());
+        assert_eq!("(搜索历史)`py`：python web", state.display());
+
+        // 再按 Ctrl-R 找更早的
+        state.reverse_search(&history);
+        assert_eq!("python", state.text());
+        state.reverse_search(&history);
+        assert_eq!("python", state.text());
+
+        // 删除字符后从最近的开始找
+        state.reverse_input(&history, None);
+        assert_eq!("python web", state.text());
+        state.reverse_input(&history, Some('$'));
+        assert_eq!("", state.text());
+
+        state.finish_reverse(false);
+        assert_eq!("go", state.text());
+
+        state.reverse_search(&history);
+        state.reverse_input(&history, Some('7'));
+        assert_eq!(SearchMode::Volume, state.finish_reverse(true));
+        assert_eq!("#72", state.text());
+        assert!(!state.is_reverse_searching());
+    }
+}
@@ -1,7 +1,11 @@
+pub mod completion;
 pub mod content;
+pub mod editor;
+pub mod halfblock;
 pub mod input;
 pub mod popup;
 pub mod projectdetail;
+pub mod scrollbar;
 pub mod statusline;
 
 pub use content::{Content, ContentState};
@@ -7,6 +7,9 @@ use tui::widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, Widget};
 
 use crate::app_global::THEME;
 use crate::events::Message;
+use crate::utils;
+
+use super::scrollbar::{render_scrolled, ScrollState};
 
 ///
 ///
@@ -16,6 +19,17 @@ pub struct Popup {}
 #[derive(Debug, Default)]
 pub struct PopupState {
     pub msg: Message,
+    /// 内容比弹窗高时的滚动位置
+    pub scroll: ScrollState,
+}
+
+impl PopupState {
+    pub fn new(msg: Message) -> Self {
+        PopupState {
+            msg,
+            scroll: ScrollState::default(),
+        }
+    }
 }
 
 impl StatefulWidget for Popup {
@@ -49,10 +63,44 @@ impl StatefulWidget for Popup {
             .title(title)
             .borders(Borders::ALL);
 
-        Paragraph::new(format!("\n{}\n\n\n☟ 按任何键关闭窗口...", msg))
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        // 放得下时和原来一样整段居中，放不下时最后一行留给提示，上面的内容可以滚动
+        let text = format!("\n{}\n\n\n☟ 按任何键关闭窗口...", msg);
+        let lines = utils::wrap_lines(&text, inner.width as usize);
+        if lines.len() <= inner.height as usize {
+            state.scroll.update(lines.len(), inner.height as usize);
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .style(style)
+                .render(inner, buf);
+            return;
+        }
+
+        let text = format!("\n{}", msg);
+        let text_area = Rect {
+            height: inner.height.saturating_sub(1),
+            ..inner
+        };
+        render_scrolled(
+            text_area,
+            buf,
+            &mut state.scroll,
+            style,
+            Alignment::Center,
+            |width| utils::wrap_lines(&text, width as usize),
+        );
+        Paragraph::new("☟ j/k 滚动，其他键关闭窗口...")
             .alignment(Alignment::Center)
             .style(style)
-            .block(block)
-            .render(area, buf);
+            .render(
+                Rect {
+                    y: inner.bottom().saturating_sub(1),
+                    height: inner.height.min(1),
+                    ..inner
+                },
+                buf,
+            );
     }
 }
@@ -1,15 +1,56 @@
+use std::sync::Arc;
+
+use image::DynamicImage;
 use tui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, StatefulWidget, Widget},
+    style::Style,
+    text::Spans,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, StatefulWidget, Widget, Wrap},
 };
 
-use crate::{app_global::THEME, theme::choose_font_style, utils};
+use crate::{app_global::THEME, note::Note, readme, theme::choose_font_style, utils};
 
 use super::content::{Category, Project};
+use super::halfblock::{HalfBlock, HalfBlockState};
+use super::scrollbar::{render_scrolled, ScrollState};
 
 /// 项目明细
-pub struct ProjectDetail {}
+pub struct ProjectDetail {
+    /// 截图由 kitty、sixel 协议在绘制之后输出，这里只留出位置
+    pub graphics: bool,
+}
+
+/// 截图的加载状态
+#[derive(Debug, Default)]
+pub enum ImageState {
+    /// 没有截图或者没有开启截图
+    #[default]
+    None,
+
+    Loading,
+
+    Loaded(Arc<DynamicImage>),
+
+    /// 加载失败的原因
+    Failed(String),
+}
+
+/// README 的加载状态
+#[derive(Debug, Default)]
+pub enum ReadmeState {
+    /// 还没有打开过 README
+    #[default]
+    None,
+
+    Loading,
+
+    /// 渲染好的文本行，还没有折行
+    Loaded(Vec<Spans<'static>>),
+
+    /// 加载失败的原因
+    Failed(String),
+}
 
 #[derive(Debug, Default)]
 pub struct ProjectDetailState {
@@ -20,6 +61,23 @@ pub struct ProjectDetailState {
     fork: String,
     desc: String,
     category: Category,
+    /// 自己写的笔记
+    note: String,
+    /// 自己打的标签
+    tags: Vec<String>,
+    /// 截图地址
+    image_src: Option<String>,
+    image: ImageState,
+    /// 上次绘制时截图的位置
+    image_area: Option<Rect>,
+    /// 半格字符画截图时缩放好的图片
+    half_block: HalfBlockState,
+    /// 是否展示 README，展示时不展示截图
+    show_readme: bool,
+    readme: ReadmeState,
+    /// 简介和 README 各自滚动到的位置
+    desc_scroll: ScrollState,
+    readme_scroll: ScrollState,
 }
 
 impl From<Project> for ProjectDetailState {
@@ -38,6 +96,93 @@ impl From<Project> for ProjectDetailState {
             fork: project.fork,
             desc: project.desc,
             category,
+            note: String::new(),
+            tags: Vec::new(),
+            image_src: project.image,
+            image: ImageState::None,
+            image_area: None,
+            half_block: HalfBlockState::default(),
+            show_readme: false,
+            readme: ReadmeState::None,
+            desc_scroll: ScrollState::default(),
+            readme_scroll: ScrollState::default(),
+        }
+    }
+}
+
+impl ProjectDetailState {
+    pub fn set_note(&mut self, note: Option<&Note>) {
+        self.note = note.map(|note| note.text.clone()).unwrap_or_default();
+        self.tags = note.map(|note| note.tags.clone()).unwrap_or_default();
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn image_src(&self) -> Option<&str> {
+        self.image_src.as_deref()
+    }
+
+    /// 开始加载截图，加载完之前先留出位置
+    pub fn set_loading(&mut self) {
+        self.image = ImageState::Loading;
+    }
+
+    /// 截图加载完成，已经切换到其他项目时丢弃
+    pub fn set_image(&mut self, src: &str, result: Result<Arc<DynamicImage>, String>) {
+        if self.image_src() != Some(src) || matches!(self.image, ImageState::None) {
+            return;
+        }
+        self.image = match result {
+            Ok(image) => ImageState::Loaded(image),
+            Err(e) => ImageState::Failed(e),
+        };
+    }
+
+    pub fn has_image(&self) -> bool {
+        !matches!(self.image, ImageState::None)
+    }
+
+    pub fn is_showing_readme(&self) -> bool {
+        self.show_readme
+    }
+
+    /// 切换 README，第一次打开时返回需要下载 README 的项目地址
+    pub fn toggle_readme(&mut self) -> Option<String> {
+        self.show_readme = !self.show_readme;
+        if self.show_readme && matches!(self.readme, ReadmeState::None) {
+            self.readme = ReadmeState::Loading;
+            return Some(self.url.clone());
+        }
+        None
+    }
+
+    /// README 下载完成，已经切换到其他项目时丢弃
+    pub fn set_readme(&mut self, url: &str, result: Result<String, String>) {
+        if self.url != url || !matches!(self.readme, ReadmeState::Loading) {
+            return;
+        }
+        self.readme = match result {
+            Ok(markdown) => ReadmeState::Loaded(readme::render(&markdown, THEME.get().unwrap())),
+            Err(e) => ReadmeState::Failed(e),
+        };
+    }
+
+    /// 展示 README 时滚动 README，否则滚动简介
+    pub fn scroll_mut(&mut self) -> &mut ScrollState {
+        if self.show_readme {
+            &mut self.readme_scroll
+        } else {
+            &mut self.desc_scroll
+        }
+    }
+
+    /// 需要用 kitty、sixel 协议输出的截图和位置
+    pub fn placement(&self) -> Option<(&str, &Arc<DynamicImage>, Rect)> {
+        match (&self.image, self.image_src(), self.image_area) {
+            (ImageState::Loaded(image), Some(src), Some(area)) => Some((src, image, area)),
+            _ => None,
         }
     }
 }
@@ -125,23 +270,149 @@ impl StatefulWidget for ProjectDetail {
             .style(style)
             .render(project_stars_layout[2], buf);
 
-        let desc = state.desc.clone();
-        let desc_wrap = utils::wrap_lines(desc, 114);
+        // 有截图或者 README 时简介只占需要的高度，剩下的留给它们
+        let constraints = if state.has_image() || state.show_readme {
+            let desc_height = utils::wrap_lines(&state.desc, layout[2].width as usize).len();
+            vec![
+                Constraint::Length(desc_height as u16 + 2),
+                Constraint::Min(3),
+                Constraint::Length(6),
+            ]
+        } else {
+            vec![Constraint::Min(3), Constraint::Length(6)]
+        };
+        let desc_layout = Layout::default().constraints(constraints).split(layout[2]);
+
+        let block = Block::default()
+            .title(" 🍗 简介 ")
+            .title_alignment(Alignment::Center)
+            .borders(Borders::TOP);
+        let desc_area = block.inner(desc_layout[0]);
+        block.style(style).render(desc_layout[0], buf);
+        let desc = &state.desc;
+        render_scrolled(
+            desc_area,
+            buf,
+            &mut state.desc_scroll,
+            style,
+            Alignment::Left,
+            |width| utils::wrap_lines(desc, width as usize),
+        );
+
+        state.image_area = None;
+        if state.show_readme {
+            render_readme(desc_layout[1], buf, state, style);
+        } else if state.has_image() {
+            let block = Block::default()
+                .title(" 🖼 截图 ")
+                .title_alignment(Alignment::Center)
+                .borders(Borders::TOP);
+            let image_area = block.inner(desc_layout[1]);
+            block.style(style).render(desc_layout[1], buf);
 
-        Paragraph::new(desc_wrap)
+            match &state.image {
+                ImageState::Loaded(_) if self.graphics => state.image_area = Some(image_area),
+                ImageState::Loaded(image) => {
+                    HalfBlock { image }.render(image_area, buf, &mut state.half_block)
+                }
+                ImageState::Loading => Paragraph::new("截图加载中…")
+                    .alignment(Alignment::Center)
+                    .style(style)
+                    .render(image_area, buf),
+                ImageState::Failed(e) => Paragraph::new(format!("截图加载失败：{}", e))
+                    .alignment(Alignment::Center)
+                    .style(style)
+                    .render(image_area, buf),
+                ImageState::None => {}
+            }
+        }
+
+        let note = if state.note.is_empty() && state.tags.is_empty() {
+            "按 i 添加笔记和标签，按 r 查看 README".to_string()
+        } else {
+            format!("🏷 {}\n{}", state.tags.join(" "), state.note)
+        };
+        Paragraph::new(note)
             .block(
                 Block::default()
-                    .title(" 🍗 简介 ")
+                    .title(" 📝 笔记 ")
                     .title_alignment(Alignment::Center)
                     .borders(Borders::TOP),
             )
             .style(style)
-            .render(layout[2], buf);
+            .render(desc_layout[desc_layout.len() - 1], buf);
+    }
+}
+
+fn render_readme(area: Rect, buf: &mut Buffer, state: &mut ProjectDetailState, style: Style) {
+    let block = Block::default()
+        .title(" 📖 README ")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::TOP);
+    let inner = block.inner(area);
+    block.style(style).render(area, buf);
+
+    match &state.readme {
+        ReadmeState::Loaded(lines) => render_scrolled(
+            inner,
+            buf,
+            &mut state.readme_scroll,
+            style,
+            Alignment::Left,
+            |width| readme::wrap(lines, width),
+        ),
+        ReadmeState::Loading => Paragraph::new("README 加载中…")
+            .alignment(Alignment::Center)
+            .style(style)
+            .render(inner, buf),
+        ReadmeState::Failed(e) => Paragraph::new(format!("README 加载失败：{}", e))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(style)
+            .render(inner, buf),
+        ReadmeState::None => {}
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::*;
+
+    #[test]
+    fn test_readme_scroll() {
+        let mut state = ProjectDetailState::from(Project {
+            url: "https://github.com/rofl0r/ncdu".into(),
+            ..Default::default()
+        });
+        assert_eq!(
+            Some("https://github.com/rofl0r/ncdu".into()),
+            state.toggle_readme()
+        );
+        assert!(state.is_showing_readme());
+        // 下载中再次打开不重复下载
+        assert_eq!(None, state.toggle_readme());
+        assert_eq!(None, state.toggle_readme());
+
+        state.set_readme("https://github.com/psf/black", Err("x".into()));
+        assert!(matches!(state.readme, ReadmeState::Loading));
+        state.readme = ReadmeState::Loaded(vec![Spans::from("line"); 30]);
+
+        let mut buf = Buffer::empty(Rect::new(0, 0, 20, 11));
+        render_readme(buf.area, &mut buf, &mut state, Style::default());
+        assert_eq!(10, state.scroll_mut().page());
+        // 滚动条在最右边一列
+        assert_eq!("l", buf.get(0, 1).symbol);
+        assert_eq!("┃", buf.get(19, 1).symbol);
+        assert_eq!("│", buf.get(19, 10).symbol);
+        state.scroll_mut().bottom();
+        assert_eq!(20, state.scroll_mut().offset());
+
+        // 关掉 README 后滚动的是简介
+        state.toggle_readme();
+        assert_eq!(0, state.scroll_mut().offset());
+        state.toggle_readme();
+        assert_eq!(20, state.scroll_mut().offset());
+    }
 
     #[test]
     #[ignore]
@@ -0,0 +1,184 @@
+use tui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::Style,
+    text::Spans,
+    widgets::{Paragraph, Widget},
+};
+
+/// 可以滚动的文本的位置，行数和高度在绘制时更新
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollState {
+    /// 最上面一行是第几行
+    offset: usize,
+    /// 折行之后的总行数
+    total: usize,
+    /// 能展示的行数
+    height: usize,
+}
+
+impl ScrollState {
+    /// 绘制时更新总行数和高度，内容变短时不会滚过头
+    pub fn update(&mut self, total: usize, height: usize) {
+        self.total = total;
+        self.height = height;
+        self.offset = self.offset.min(self.max_offset());
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// 内容比展示的区域高，需要滚动条
+    pub fn is_scrollable(&self) -> bool {
+        self.total > self.height
+    }
+
+    /// 一屏的行数
+    pub fn page(&self) -> usize {
+        self.height.max(1)
+    }
+
+    fn max_offset(&self) -> usize {
+        self.total.saturating_sub(self.height)
+    }
+
+    pub fn down(&mut self, lines: usize) {
+        self.to(self.offset.saturating_add(lines));
+    }
+
+    pub fn up(&mut self, lines: usize) {
+        self.to(self.offset.saturating_sub(lines));
+    }
+
+    /// 滚动到第几行，超出时停在最后一屏
+    pub fn to(&mut self, line: usize) {
+        self.offset = line.min(self.max_offset());
+    }
+
+    pub fn bottom(&mut self) {
+        self.offset = self.max_offset();
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+}
+
+/// 竖直的滚动条，画在区域最右边一列，内容不需要滚动时不画
+pub struct Scrollbar<'a> {
+    pub state: &'a ScrollState,
+    pub style: Style,
+}
+
+impl<'a> Scrollbar<'a> {
+    /// 滑块在轨道上的起始位置和长度
+    fn thumb(&self, track: usize) -> (usize, usize) {
+        let state = self.state;
+        let len = (track * state.height / state.total.max(1)).clamp(1, track);
+        let pos = match state.max_offset() {
+            0 => 0,
+            max => (track - len) * state.offset / max,
+        };
+        (pos, len)
+    }
+}
+
+impl<'a> Widget for Scrollbar<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.area() == 0 || !self.state.is_scrollable() {
+            return;
+        }
+        let x = area.right() - 1;
+        let (pos, len) = self.thumb(area.height as usize);
+        for i in 0..area.height {
+            let symbol = if (pos..pos + len).contains(&(i as usize)) {
+                "┃"
+            } else {
+                "│"
+            };
+            buf.get_mut(x, area.y + i)
+                .set_symbol(symbol)
+                .set_style(self.style);
+        }
+    }
+}
+
+/// 按区域宽度折行后绘制，放不下时给滚动条让出最右边一列。`wrap` 把内容折成不超过指定宽度的行
+pub fn render_scrolled<'a>(
+    area: Rect,
+    buf: &mut Buffer,
+    state: &mut ScrollState,
+    style: Style,
+    alignment: Alignment,
+    wrap: impl Fn(u16) -> Vec<Spans<'a>>,
+) {
+    let mut lines = wrap(area.width);
+    if lines.len() > area.height as usize {
+        lines = wrap(area.width.saturating_sub(1));
+    }
+    state.update(lines.len(), area.height as usize);
+
+    let text_area = Rect {
+        width: area.width.saturating_sub(state.is_scrollable() as u16),
+        ..area
+    };
+    Paragraph::new(lines)
+        .scroll((state.offset() as u16, 0))
+        .alignment(alignment)
+        .style(style)
+        .render(text_area, buf);
+    Scrollbar { state, style }.render(area, buf);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scroll() {
+        let mut state = ScrollState::default();
+        state.update(30, 10);
+        assert!(state.is_scrollable());
+        assert_eq!(10, state.page());
+
+        state.down(15);
+        assert_eq!(15, state.offset());
+        state.down(state.page());
+        assert_eq!(20, state.offset());
+        state.up(100);
+        assert_eq!(0, state.offset());
+        state.bottom();
+        assert_eq!(20, state.offset());
+
+        // 窗口变高之后不会留出空白
+        state.update(30, 25);
+        assert_eq!(5, state.offset());
+        state.update(5, 10);
+        assert!(!state.is_scrollable());
+        assert_eq!(0, state.offset());
+    }
+
+    #[test]
+    fn test_scrollbar() {
+        let mut state = ScrollState::default();
+        state.update(40, 10);
+        let symbols = |state: &ScrollState| {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 3, 10));
+            Scrollbar {
+                state,
+                style: Style::default(),
+            }
+            .render(buf.area, &mut buf);
+            (0..10)
+                .map(|y| buf.get(2, y).symbol.clone())
+                .collect::<String>()
+        };
+        assert_eq!("┃┃││││││││", symbols(&state));
+        state.bottom();
+        assert_eq!("││││││││┃┃", symbols(&state));
+
+        state.update(10, 10);
+        assert_eq!(" ".repeat(10), symbols(&state));
+    }
+}
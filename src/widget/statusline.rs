@@ -5,19 +5,42 @@ use tui::{
     text::{Span, Spans},
     widgets::{Block, Borders, Paragraph, StatefulWidget, Widget},
 };
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
     app::SearchMode,
     app_global::{HG_INFO, THEME},
 };
 
+/// 加载动画
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// 翻页箭头，鼠标点击可以翻页
+const PREV_ARROW: &str = "⇦ h";
+const NEXT_ARROW: &str = "l ⇨";
+
 /// 状态栏
 pub struct StatusLine {}
 
+/// 鼠标点中的翻页箭头
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrow {
+    Prev,
+    Next,
+}
+
 #[derive(Debug)]
 pub struct StatusLineState {
     pub mode: SearchMode,
     page_no: usize,
+    /// 是否有请求正在进行
+    loading: bool,
+    /// 加载动画当前帧
+    frame: usize,
+    /// 上次绘制时钟的秒数
+    last_second: u32,
+    /// 上次绘制时前、后翻页箭头的位置，没有箭头时为 None
+    arrows: Option<(Rect, Rect)>,
 }
 
 impl Default for StatusLineState {
@@ -25,6 +48,10 @@ impl Default for StatusLineState {
         StatusLineState {
             mode: SearchMode::Normal,
             page_no: 1,
+            loading: false,
+            frame: 0,
+            last_second: 0,
+            arrows: None,
         }
     }
 }
@@ -37,21 +64,54 @@ impl StatusLineState {
     pub fn get_page_no(&self, wait_remove: String, search_mode: SearchMode) -> usize {
         match search_mode {
             SearchMode::Normal => 1,
-            SearchMode::Volume => wait_remove[1..].parse::<usize>().unwrap(),
+            SearchMode::Volume => wait_remove[1..].parse::<usize>().unwrap_or(self.page_no),
             SearchMode::Category => self.page_no,
+            SearchMode::Favorite | SearchMode::Tag => 1,
         }
     }
 
     pub fn set_page_no(&mut self, page_no: usize) {
+        let max_volume = HG_INFO.get().map_or(usize::MAX, |info| info.max_volume);
         if page_no < 1 {
             self.page_no = 1;
-        } else if page_no > HG_INFO.max_volume {
-            self.page_no = HG_INFO.max_volume;
+        } else if page_no > max_volume {
+            self.page_no = max_volume;
         } else {
             self.page_no = page_no;
         }
     }
 
+    pub fn set_loading(&mut self, loading: bool) {
+        self.loading = loading;
+        self.frame = 0;
+    }
+
+    /// 推进加载动画，返回是否需要重绘
+    pub fn tick(&mut self) -> bool {
+        let second = Local::now().second();
+        let second_change = second != self.last_second;
+        self.last_second = second;
+
+        if self.loading {
+            self.frame = (self.frame + 1) % SPINNER.len();
+            return true;
+        }
+        second_change
+    }
+
+    /// 鼠标点中的翻页箭头
+    pub fn arrow_at(&self, x: u16, y: u16) -> Option<Arrow> {
+        let (prev, next) = self.arrows?;
+        let contains = |r: Rect| x >= r.x && x < r.right() && y >= r.y && y < r.bottom();
+        if contains(prev) {
+            Some(Arrow::Prev)
+        } else if contains(next) {
+            Some(Arrow::Next)
+        } else {
+            None
+        }
+    }
+
     pub fn set_mode(&mut self, mode: SearchMode) {
         if self.mode != mode {
             // 有改变
@@ -80,11 +140,15 @@ impl StatefulWidget for StatusLine {
 
         // clock layout[2]
         let now = Local::now();
+        let (star, project_count) = match HG_INFO.get() {
+            Some(info) => (info.star.clone(), info.project_count.to_string()),
+            None => ("-".into(), "-".into()),
+        };
         Paragraph::new(format!(
             " ⏰ {} 🌟 {} 📚项目数 {} 个",
             now.format("%Y-%m-%d %H:%M:%S"),
-            HG_INFO.star,
-            HG_INFO.project_count
+            star,
+            project_count
         ))
         .style(theme_style.tips)
         .block(
@@ -98,10 +162,28 @@ impl StatefulWidget for StatusLine {
         //
         //
         let text = match state.mode {
+            _ if state.loading => format!("{} 加载中，Esc 取消", SPINNER[state.frame]),
             SearchMode::Normal => "搜索模式".into(),
-            SearchMode::Volume => format!("⇦ h   第 {} 期   l ⇨", state.page_no),
-            SearchMode::Category => format!("⇦ h   第 {} 页   l ⇨", state.page_no),
+            SearchMode::Volume => {
+                format!("{}   第 {} 期   {}", PREV_ARROW, state.page_no, NEXT_ARROW)
+            }
+            SearchMode::Category => {
+                format!("{}   第 {} 页   {}", PREV_ARROW, state.page_no, NEXT_ARROW)
+            }
+            SearchMode::Favorite => {
+                format!(
+                    "{}   收藏 第 {} 页   {}",
+                    PREV_ARROW, state.page_no, NEXT_ARROW
+                )
+            }
+            SearchMode::Tag => format!(
+                "{}   标签 第 {} 页   {}",
+                PREV_ARROW, state.page_no, NEXT_ARROW
+            ),
         };
+        state.arrows = text
+            .starts_with(PREV_ARROW)
+            .then(|| arrow_areas(layout[1], &text));
 
         Paragraph::new(text)
             .block(Block::default().borders(Borders::NONE))
@@ -126,3 +208,37 @@ impl StatefulWidget for StatusLine {
         .render(layout[0], buf);
     }
 }
+
+/// 居中显示的文字两端箭头的位置，和 tui 的 `Paragraph` 居中的算法一致
+fn arrow_areas(area: Rect, text: &str) -> (Rect, Rect) {
+    let width = text.width() as u16;
+    let x = area.x + (area.width / 2).saturating_sub(width / 2);
+    let prev = Rect::new(x, area.y, PREV_ARROW.width() as u16, 1).intersection(area);
+    let next_width = NEXT_ARROW.width() as u16;
+    let next = Rect::new(x + width - next_width, area.y, next_width, 1).intersection(area);
+    (prev, next)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arrow_at() {
+        let mut state = StatusLineState::default();
+        assert_eq!(None, state.arrow_at(0, 0));
+
+        // "⇦ h   第 72 期   l ⇨" 宽 20，在宽 41 的区域里从第 10 + 20 - 10 列开始
+        let area = Rect::new(10, 3, 41, 1);
+        state.arrows = Some(arrow_areas(area, "⇦ h   第 72 期   l ⇨"));
+        assert_eq!(None, state.arrow_at(19, 3));
+        assert_eq!(Some(Arrow::Prev), state.arrow_at(20, 3));
+        assert_eq!(Some(Arrow::Prev), state.arrow_at(22, 3));
+        assert_eq!(None, state.arrow_at(23, 3));
+        assert_eq!(None, state.arrow_at(36, 3));
+        assert_eq!(Some(Arrow::Next), state.arrow_at(37, 3));
+        assert_eq!(Some(Arrow::Next), state.arrow_at(39, 3));
+        assert_eq!(None, state.arrow_at(40, 3));
+        assert_eq!(None, state.arrow_at(20, 2));
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use crate::app::SearchMode;
+use crate::events::{HGEvent, Notify, NOTIFY};
+use crate::fetch;
+use crate::index;
+use crate::parse::{LastParse, PARSER};
+use crate::widget::content::{Category, Project};
+
+/// 在后台线程执行的请求
+#[derive(Debug, Clone, PartialEq)]
+pub enum Job {
+    /// 展示最新一期
+    Latest,
+
+    /// 搜索，搜索内容和搜索模式
+    Search(String, SearchMode),
+
+    /// 翻页，搜索模式、当前类别和页数
+    Page(SearchMode, Option<Category>, usize),
+
+    /// 请求一整期用来标记为已读，不改变展示的内容
+    MarkVolume(usize),
+}
+
+/// 请求的结果，错误信息直接用于弹窗展示
+pub type JobResult = Result<(Vec<Project>, LastParse), String>;
+
+/// 在后台线程请求并解析页面，完成后通过 `NOTIFY` 通知界面，`id` 用来丢弃过期的结果
+pub fn spawn(id: u64, job: Job) {
+    std::thread::spawn(move || {
+        let result = run(&job).map_err(|e| e.to_string());
+        NOTIFY
+            .0
+            .send(HGEvent::NotifyEvent(Notify::Fetched(id, job, result)))
+            .unwrap();
+    });
+}
+
+/// 请求并解析页面，会阻塞当前线程
+pub fn run(job: &Job) -> Result<(Vec<Project>, LastParse)> {
+    // 同步过本地索引时，关键字搜索不再请求网站
+    if let (Job::Search(wait_search, SearchMode::Normal), Some(index)) = (job, index::get()) {
+        return Ok((index.search(wait_search), LastParse::Search));
+    }
+
+    let (text, mode) = match job {
+        Job::Latest => (
+            fetch::fetch_volume(fetch::hg_info()?.max_volume)?,
+            SearchMode::Volume,
+        ),
+        Job::Search(wait_search, mode) => (fetch::fetch(wait_search.clone(), *mode)?, *mode),
+        Job::Page(SearchMode::Volume, _, page_no) | Job::MarkVolume(page_no) => {
+            (fetch::fetch_volume(*page_no)?, SearchMode::Volume)
+        }
+        Job::Page(SearchMode::Category, Some(category), page_no) => (
+            fetch::fetch_category(*category, *page_no)?,
+            SearchMode::Category,
+        ),
+        Job::Page(..) => return Ok((vec![], LastParse::Search)),
+    };
+
+    Ok(PARSER.get(&mode).unwrap().parse(text)?)
+}